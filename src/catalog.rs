@@ -0,0 +1,743 @@
+use anyhow::Error;
+use serde::Serialize;
+use sqlx::mysql::MySqlPool;
+
+use crate::ident::QualifiedName;
+use crate::mask::View;
+
+/// Schemas the server keeps for itself.
+pub(crate) const SYSTEM_SCHEMAS: &[&str] =
+    &["information_schema", "mysql", "performance_schema", "sys"];
+
+/// A schema as reported by `list_schemas`.
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct SchemaInfo {
+    pub(crate) name: String,
+    pub(crate) charset: String,
+    pub(crate) collation: String,
+    pub(crate) table_count: i64,
+    pub(crate) view_count: i64,
+    /// Data and index size of its tables, as estimated by the engines.
+    pub(crate) size_bytes: i64,
+    /// Whether it is the connection's current schema.
+    #[sqlx(default)]
+    pub(crate) current: bool,
+}
+
+/// Everything `describe` reports about a table or view.
+#[derive(Debug, Serialize)]
+pub(crate) struct TableDescription {
+    pub(crate) schema: String,
+    pub(crate) name: String,
+    /// `BASE TABLE`, `VIEW` or `SYSTEM VIEW`.
+    pub(crate) table_type: String,
+    pub(crate) engine: Option<String>,
+    /// The storage engine's estimate, exact only for MyISAM tables.
+    pub(crate) row_estimate: Option<i64>,
+    pub(crate) comment: Option<String>,
+    pub(crate) columns: Vec<ColumnInfo>,
+    pub(crate) primary_key: Vec<String>,
+    pub(crate) indexes: Vec<IndexInfo>,
+    /// Foreign keys of this table.
+    pub(crate) foreign_keys: Vec<ForeignKey>,
+    /// Foreign keys of other tables that reference this one.
+    pub(crate) referenced_by: Vec<ForeignKey>,
+    pub(crate) checks: Vec<CheckInfo>,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct ColumnInfo {
+    pub(crate) column_name: String,
+    pub(crate) data_type: String,
+    /// The full type, e.g. `int unsigned` or `enum('new','paid')`.
+    pub(crate) column_type: String,
+    pub(crate) character_maximum_length: Option<i64>,
+    pub(crate) column_default: Option<String>,
+    pub(crate) is_nullable: String,
+    /// e.g. `auto_increment` or `VIRTUAL GENERATED`.
+    pub(crate) extra: Option<String>,
+    pub(crate) collation: Option<String>,
+    pub(crate) comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct IndexInfo {
+    pub(crate) name: String,
+    pub(crate) unique: bool,
+    /// `BTREE`, `HASH`, `FULLTEXT` or `SPATIAL`.
+    pub(crate) index_type: String,
+    /// In index order, with prefix lengths as in `name(10)`.
+    pub(crate) columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ForeignKey {
+    pub(crate) name: String,
+    pub(crate) schema: String,
+    pub(crate) table: String,
+    pub(crate) columns: Vec<String>,
+    pub(crate) referenced_schema: String,
+    pub(crate) referenced_table: String,
+    pub(crate) referenced_columns: Vec<String>,
+    pub(crate) on_update: String,
+    pub(crate) on_delete: String,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct CheckInfo {
+    pub(crate) name: String,
+    pub(crate) clause: String,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct ViewInfo {
+    pub(crate) name: String,
+    /// The `SELECT` of the view, `None` without the privileges to see it.
+    pub(crate) definition: Option<String>,
+    pub(crate) is_updatable: String,
+    /// `NONE`, `LOCAL` or `CASCADED`.
+    pub(crate) check_option: String,
+    pub(crate) security_type: String,
+    pub(crate) definer: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RoutineInfo {
+    pub(crate) name: String,
+    /// `PROCEDURE` or `FUNCTION`.
+    pub(crate) routine_type: String,
+    /// e.g. `add_item(IN order_id INT, OUT total DECIMAL(10,2))`.
+    pub(crate) signature: String,
+    /// Return type of a function.
+    pub(crate) returns: Option<String>,
+    pub(crate) deterministic: bool,
+    /// e.g. `READS SQL DATA`.
+    pub(crate) data_access: String,
+    pub(crate) security_type: String,
+    /// The body, `None` without the privileges to see it.
+    pub(crate) definition: Option<String>,
+    pub(crate) comment: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct TriggerInfo {
+    pub(crate) name: String,
+    pub(crate) table: String,
+    /// `BEFORE` or `AFTER`.
+    pub(crate) timing: String,
+    /// `INSERT`, `UPDATE` or `DELETE`.
+    pub(crate) event: String,
+    pub(crate) action_order: i64,
+    pub(crate) statement: String,
+}
+
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub(crate) struct EventInfo {
+    pub(crate) name: String,
+    /// `ONE TIME` or `RECURRING`.
+    pub(crate) event_type: String,
+    pub(crate) execute_at: Option<String>,
+    /// e.g. `1 DAY` for a recurring event.
+    pub(crate) interval: Option<String>,
+    pub(crate) starts: Option<String>,
+    pub(crate) ends: Option<String>,
+    /// `ENABLED`, `DISABLED` or `SLAVESIDE_DISABLED`.
+    pub(crate) status: String,
+    pub(crate) last_executed: Option<String>,
+    pub(crate) definition: String,
+    pub(crate) comment: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TableRow {
+    table_schema: String,
+    table_name: String,
+    table_type: String,
+    engine: Option<String>,
+    table_rows: Option<i64>,
+    table_comment: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct IndexRow {
+    index_name: String,
+    non_unique: i64,
+    index_type: String,
+    column_name: Option<String>,
+    sub_part: Option<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ForeignKeyRow {
+    constraint_name: String,
+    table_schema: String,
+    table_name: String,
+    column_name: String,
+    referenced_table_schema: String,
+    referenced_table_name: String,
+    referenced_column_name: String,
+    update_rule: String,
+    delete_rule: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RoutineRow {
+    name: String,
+    routine_type: String,
+    is_deterministic: String,
+    data_access: String,
+    security_type: String,
+    definition: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ParameterRow {
+    routine_name: String,
+    routine_type: String,
+    /// 0 for the return value of a function.
+    position: i64,
+    mode: Option<String>,
+    name: Option<String>,
+    data_type: String,
+}
+
+/// Describe `table`, failing if it doesn't exist.
+pub(crate) async fn describe_table(
+    pool: &MySqlPool,
+    table: &QualifiedName,
+) -> Result<TableDescription, Error> {
+    let query = r#"
+      SELECT
+        TABLE_SCHEMA AS table_schema,
+        TABLE_NAME AS table_name,
+        TABLE_TYPE AS table_type,
+        ENGINE AS engine,
+        CAST(TABLE_ROWS AS SIGNED) AS table_rows,
+        NULLIF(TABLE_COMMENT, '') AS table_comment
+      FROM information_schema.tables
+      WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+    "#;
+    let row = sqlx::query_as::<_, TableRow>(query)
+        .bind(&table.schema)
+        .bind(&table.name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Table {} does not exist", table))?;
+    // as stored, which may differ in case from what was asked for
+    let schema = row.table_schema;
+    let name = row.table_name;
+
+    let query = r#"
+      SELECT
+        COLUMN_NAME AS column_name,
+        CAST(DATA_TYPE AS CHAR) AS data_type,
+        CAST(COLUMN_TYPE AS CHAR) AS column_type,
+        CHARACTER_MAXIMUM_LENGTH AS character_maximum_length,
+        CAST(COLUMN_DEFAULT AS CHAR) AS column_default,
+        IS_NULLABLE AS is_nullable,
+        NULLIF(EXTRA, '') AS extra,
+        COLLATION_NAME AS collation,
+        NULLIF(CAST(COLUMN_COMMENT AS CHAR), '') AS comment
+      FROM information_schema.columns
+      WHERE table_schema = ? AND table_name = ?
+      ORDER BY ordinal_position
+    "#;
+    let columns = sqlx::query_as::<_, ColumnInfo>(query)
+        .bind(&schema)
+        .bind(&name)
+        .fetch_all(pool)
+        .await?;
+
+    let query = r#"
+      SELECT
+        INDEX_NAME AS index_name,
+        CAST(NON_UNIQUE AS SIGNED) AS non_unique,
+        INDEX_TYPE AS index_type,
+        COLUMN_NAME AS column_name,
+        CAST(SUB_PART AS SIGNED) AS sub_part
+      FROM information_schema.statistics
+      WHERE table_schema = ? AND table_name = ?
+      ORDER BY INDEX_NAME = 'PRIMARY' DESC, INDEX_NAME, SEQ_IN_INDEX
+    "#;
+    let indexes = group_indexes(
+        sqlx::query_as::<_, IndexRow>(query)
+            .bind(&schema)
+            .bind(&name)
+            .fetch_all(pool)
+            .await?,
+    );
+    let primary_key = indexes
+        .iter()
+        .find(|index| index.name == "PRIMARY")
+        .map(|index| index.columns.clone())
+        .unwrap_or_default();
+
+    let query = r#"
+      SELECT
+        k.CONSTRAINT_NAME AS constraint_name,
+        k.TABLE_SCHEMA AS table_schema,
+        k.TABLE_NAME AS table_name,
+        k.COLUMN_NAME AS column_name,
+        k.REFERENCED_TABLE_SCHEMA AS referenced_table_schema,
+        k.REFERENCED_TABLE_NAME AS referenced_table_name,
+        k.REFERENCED_COLUMN_NAME AS referenced_column_name,
+        r.UPDATE_RULE AS update_rule,
+        r.DELETE_RULE AS delete_rule
+      FROM information_schema.key_column_usage k
+      JOIN information_schema.referential_constraints r
+        ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA
+        AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME
+        AND r.TABLE_NAME = k.TABLE_NAME
+      WHERE k.REFERENCED_TABLE_NAME IS NOT NULL
+        AND ((k.TABLE_SCHEMA = ? AND k.TABLE_NAME = ?)
+          OR (k.REFERENCED_TABLE_SCHEMA = ? AND k.REFERENCED_TABLE_NAME = ?))
+      ORDER BY k.TABLE_SCHEMA, k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION
+    "#;
+    let rows = sqlx::query_as::<_, ForeignKeyRow>(query)
+        .bind(&schema)
+        .bind(&name)
+        .bind(&schema)
+        .bind(&name)
+        .fetch_all(pool)
+        .await?;
+    let mut foreign_keys = Vec::new();
+    let mut referenced_by = Vec::new();
+    for fk in group_foreign_keys(rows) {
+        // a self reference is both
+        if fk.referenced_schema == schema && fk.referenced_table == name {
+            referenced_by.push(fk.clone());
+        }
+        if fk.schema == schema && fk.table == name {
+            foreign_keys.push(fk);
+        }
+    }
+
+    let checks = check_constraints(pool, &schema, &name).await?;
+
+    Ok(TableDescription {
+        schema,
+        name,
+        table_type: row.table_type,
+        engine: row.engine,
+        row_estimate: row.table_rows,
+        comment: row.table_comment,
+        columns,
+        primary_key,
+        indexes,
+        foreign_keys,
+        referenced_by,
+        checks,
+    })
+}
+
+/// Every schema the connection can see, with its table count and size.
+pub(crate) async fn list_schemas(pool: &MySqlPool) -> Result<Vec<SchemaInfo>, Error> {
+    let query = r#"
+      SELECT
+        s.SCHEMA_NAME AS name,
+        s.DEFAULT_CHARACTER_SET_NAME AS charset,
+        s.DEFAULT_COLLATION_NAME AS collation,
+        CAST(COALESCE(SUM(t.TABLE_TYPE = 'BASE TABLE'), 0) AS SIGNED) AS table_count,
+        CAST(COALESCE(SUM(t.TABLE_TYPE = 'VIEW'), 0) AS SIGNED) AS view_count,
+        CAST(COALESCE(SUM(t.DATA_LENGTH + t.INDEX_LENGTH), 0) AS SIGNED) AS size_bytes
+      FROM information_schema.schemata s
+      LEFT JOIN information_schema.tables t ON t.TABLE_SCHEMA = s.SCHEMA_NAME
+      GROUP BY s.SCHEMA_NAME, s.DEFAULT_CHARACTER_SET_NAME, s.DEFAULT_COLLATION_NAME
+      ORDER BY s.SCHEMA_NAME
+    "#;
+    let mut schemas: Vec<SchemaInfo> = sqlx::query_as(query).fetch_all(pool).await?;
+    let current: Option<String> = sqlx::query_scalar("SELECT DATABASE()")
+        .fetch_one(pool)
+        .await?;
+    for schema in &mut schemas {
+        schema.current = current.as_ref() == Some(&schema.name);
+    }
+    Ok(schemas)
+}
+
+pub(crate) async fn list_views(pool: &MySqlPool, schema: &str) -> Result<Vec<ViewInfo>, Error> {
+    let query = r#"
+      SELECT
+        TABLE_NAME AS name,
+        NULLIF(CAST(VIEW_DEFINITION AS CHAR), '') AS definition,
+        IS_UPDATABLE AS is_updatable,
+        CHECK_OPTION AS check_option,
+        SECURITY_TYPE AS security_type,
+        DEFINER AS definer
+      FROM information_schema.views
+      WHERE TABLE_SCHEMA = ?
+      ORDER BY TABLE_NAME
+    "#;
+    Ok(sqlx::query_as(query).bind(schema).fetch_all(pool).await?)
+}
+
+/// `relation` as a view with the tables it reads, nested views followed, or
+/// `None` when it is no view.
+pub(crate) async fn view_tables(
+    pool: &MySqlPool,
+    relation: &QualifiedName,
+) -> Result<Option<View>, Error> {
+    let query = r#"
+      SELECT COUNT(*) FROM information_schema.views
+      WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?
+    "#;
+    let count: i64 = sqlx::query_scalar(query)
+        .bind(&relation.schema)
+        .bind(&relation.name)
+        .fetch_one(pool)
+        .await?;
+    if count == 0 {
+        return Ok(None);
+    }
+
+    let query = r#"
+      SELECT TABLE_SCHEMA, TABLE_NAME FROM information_schema.view_table_usage
+      WHERE VIEW_SCHEMA = COALESCE(?, DATABASE()) AND VIEW_NAME = ?
+    "#;
+    let mut tables: Vec<QualifiedName> = Vec::new();
+    let mut pending = vec![relation.clone()];
+    while let Some(view) = pending.pop() {
+        let rows = match sqlx::query_as::<_, (String, String)>(query)
+            .bind(&view.schema)
+            .bind(&view.name)
+            .fetch_all(pool)
+            .await
+        {
+            // before MySQL 8.0.13 and on MariaDB there is no view_table_usage
+            Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42S02") => {
+                return Ok(Some(View {
+                    name: relation.clone(),
+                    tables: None,
+                }));
+            }
+            result => result?,
+        };
+        for (schema, name) in rows {
+            let table = QualifiedName {
+                schema: Some(schema),
+                name,
+            };
+            if !tables.contains(&table) {
+                pending.push(table.clone());
+                tables.push(table);
+            }
+        }
+    }
+    Ok(Some(View {
+        name: relation.clone(),
+        tables: Some(tables),
+    }))
+}
+
+/// Procedures and functions of `schema`, with their parameter signatures.
+pub(crate) async fn list_routines(
+    pool: &MySqlPool,
+    schema: &str,
+    routine_type: &str,
+) -> Result<Vec<RoutineInfo>, Error> {
+    let query = r#"
+      SELECT
+        ROUTINE_NAME AS name,
+        ROUTINE_TYPE AS routine_type,
+        IS_DETERMINISTIC AS is_deterministic,
+        SQL_DATA_ACCESS AS data_access,
+        SECURITY_TYPE AS security_type,
+        CAST(ROUTINE_DEFINITION AS CHAR) AS definition,
+        NULLIF(CAST(ROUTINE_COMMENT AS CHAR), '') AS comment
+      FROM information_schema.routines
+      WHERE ROUTINE_SCHEMA = ? AND ROUTINE_TYPE = ?
+      ORDER BY ROUTINE_NAME
+    "#;
+    let routines = sqlx::query_as::<_, RoutineRow>(query)
+        .bind(schema)
+        .bind(routine_type)
+        .fetch_all(pool)
+        .await?;
+
+    let query = r#"
+      SELECT
+        SPECIFIC_NAME AS routine_name,
+        ROUTINE_TYPE AS routine_type,
+        CAST(ORDINAL_POSITION AS SIGNED) AS position,
+        PARAMETER_MODE AS mode,
+        PARAMETER_NAME AS name,
+        CAST(DTD_IDENTIFIER AS CHAR) AS data_type
+      FROM information_schema.parameters
+      WHERE SPECIFIC_SCHEMA = ? AND ROUTINE_TYPE = ?
+      ORDER BY SPECIFIC_NAME, ORDINAL_POSITION
+    "#;
+    let parameters = sqlx::query_as::<_, ParameterRow>(query)
+        .bind(schema)
+        .bind(routine_type)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(routines
+        .into_iter()
+        .map(|routine| {
+            let parameters: Vec<&ParameterRow> = parameters
+                .iter()
+                .filter(|p| {
+                    p.routine_name == routine.name && p.routine_type == routine.routine_type
+                })
+                .collect();
+            RoutineInfo {
+                signature: signature(&routine.name, &parameters),
+                returns: parameters
+                    .iter()
+                    .find(|p| p.position == 0)
+                    .map(|p| p.data_type.clone()),
+                name: routine.name,
+                routine_type: routine.routine_type,
+                deterministic: routine.is_deterministic == "YES",
+                data_access: routine.data_access,
+                security_type: routine.security_type,
+                definition: routine.definition,
+                comment: routine.comment,
+            }
+        })
+        .collect())
+}
+
+pub(crate) async fn list_triggers(
+    pool: &MySqlPool,
+    schema: &str,
+) -> Result<Vec<TriggerInfo>, Error> {
+    let query = r#"
+      SELECT
+        TRIGGER_NAME AS name,
+        EVENT_OBJECT_TABLE AS `table`,
+        ACTION_TIMING AS timing,
+        EVENT_MANIPULATION AS event,
+        CAST(ACTION_ORDER AS SIGNED) AS action_order,
+        CAST(ACTION_STATEMENT AS CHAR) AS statement
+      FROM information_schema.triggers
+      WHERE TRIGGER_SCHEMA = ?
+      ORDER BY EVENT_OBJECT_TABLE, ACTION_TIMING, EVENT_MANIPULATION, ACTION_ORDER
+    "#;
+    Ok(sqlx::query_as(query).bind(schema).fetch_all(pool).await?)
+}
+
+pub(crate) async fn list_events(pool: &MySqlPool, schema: &str) -> Result<Vec<EventInfo>, Error> {
+    let query = r#"
+      SELECT
+        EVENT_NAME AS name,
+        EVENT_TYPE AS event_type,
+        CAST(EXECUTE_AT AS CHAR) AS execute_at,
+        CONCAT(INTERVAL_VALUE, ' ', INTERVAL_FIELD) AS `interval`,
+        CAST(STARTS AS CHAR) AS starts,
+        CAST(ENDS AS CHAR) AS ends,
+        STATUS AS status,
+        CAST(LAST_EXECUTED AS CHAR) AS last_executed,
+        CAST(EVENT_DEFINITION AS CHAR) AS definition,
+        NULLIF(EVENT_COMMENT, '') AS comment
+      FROM information_schema.events
+      WHERE EVENT_SCHEMA = ?
+      ORDER BY EVENT_NAME
+    "#;
+    Ok(sqlx::query_as(query).bind(schema).fetch_all(pool).await?)
+}
+
+/// `name(MODE name TYPE, ...)` from the rows of `information_schema.parameters`.
+/// Function parameters are always IN, so their mode is left out.
+fn signature(name: &str, parameters: &[&ParameterRow]) -> String {
+    let parameters: Vec<String> = parameters
+        .iter()
+        .filter(|p| p.position > 0)
+        .map(|p| {
+            let mut parameter = String::new();
+            let mode = p.mode.as_ref().filter(|_| p.routine_type == "PROCEDURE");
+            if let Some(mode) = mode {
+                parameter.push_str(mode);
+                parameter.push(' ');
+            }
+            parameter.push_str(p.name.as_deref().unwrap_or_default());
+            parameter.push(' ');
+            parameter.push_str(&p.data_type);
+            parameter
+        })
+        .collect();
+    format!("{}({})", name, parameters.join(", "))
+}
+
+/// CHECK constraints of a table. Servers before MySQL 8.0.16 have none.
+async fn check_constraints(
+    pool: &MySqlPool,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<CheckInfo>, Error> {
+    let query = r#"
+      SELECT
+        tc.CONSTRAINT_NAME AS name,
+        CAST(cc.CHECK_CLAUSE AS CHAR) AS clause
+      FROM information_schema.table_constraints tc
+      JOIN information_schema.check_constraints cc
+        ON cc.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA
+        AND cc.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+      WHERE tc.CONSTRAINT_TYPE = 'CHECK' AND tc.TABLE_SCHEMA = ? AND tc.TABLE_NAME = ?
+      ORDER BY tc.CONSTRAINT_NAME
+    "#;
+    let result = sqlx::query_as::<_, CheckInfo>(query)
+        .bind(schema)
+        .bind(table)
+        .fetch_all(pool)
+        .await;
+    match result {
+        // unknown information_schema table
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42S02") => Ok(Vec::new()),
+        result => Ok(result?),
+    }
+}
+
+/// Fold the rows of `information_schema.statistics`, one per indexed
+/// column in index order, into indexes.
+fn group_indexes(rows: Vec<IndexRow>) -> Vec<IndexInfo> {
+    let mut indexes: Vec<IndexInfo> = Vec::new();
+    for row in rows {
+        let column = match (row.column_name, row.sub_part) {
+            (Some(column), Some(length)) => format!("{}({})", column, length),
+            (Some(column), None) => column,
+            // functional key parts have no column
+            (None, _) => "(expression)".to_string(),
+        };
+        match indexes
+            .last_mut()
+            .filter(|index| index.name == row.index_name)
+        {
+            Some(index) => index.columns.push(column),
+            None => indexes.push(IndexInfo {
+                name: row.index_name,
+                unique: row.non_unique == 0,
+                index_type: row.index_type,
+                columns: vec![column],
+            }),
+        }
+    }
+    indexes
+}
+
+/// Fold the rows of `information_schema.key_column_usage`, one per column
+/// in constraint order, into foreign keys.
+fn group_foreign_keys(rows: Vec<ForeignKeyRow>) -> Vec<ForeignKey> {
+    let mut keys: Vec<ForeignKey> = Vec::new();
+    for row in rows {
+        let last = keys.last_mut().filter(|fk| {
+            fk.name == row.constraint_name
+                && fk.schema == row.table_schema
+                && fk.table == row.table_name
+        });
+        match last {
+            Some(fk) => {
+                fk.columns.push(row.column_name);
+                fk.referenced_columns.push(row.referenced_column_name);
+            }
+            None => keys.push(ForeignKey {
+                name: row.constraint_name,
+                schema: row.table_schema,
+                table: row.table_name,
+                columns: vec![row.column_name],
+                referenced_schema: row.referenced_table_schema,
+                referenced_table: row.referenced_table_name,
+                referenced_columns: vec![row.referenced_column_name],
+                on_update: row.update_rule,
+                on_delete: row.delete_rule,
+            }),
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_row(name: &str, non_unique: i64, column: &str, sub_part: Option<i64>) -> IndexRow {
+        IndexRow {
+            index_name: name.to_string(),
+            non_unique,
+            index_type: "BTREE".to_string(),
+            column_name: Some(column.to_string()),
+            sub_part,
+        }
+    }
+
+    fn fk_row(name: &str, table: &str, column: &str, referenced: &str) -> ForeignKeyRow {
+        ForeignKeyRow {
+            constraint_name: name.to_string(),
+            table_schema: "shop".to_string(),
+            table_name: table.to_string(),
+            column_name: column.to_string(),
+            referenced_table_schema: "shop".to_string(),
+            referenced_table_name: "orders".to_string(),
+            referenced_column_name: referenced.to_string(),
+            update_rule: "RESTRICT".to_string(),
+            delete_rule: "CASCADE".to_string(),
+        }
+    }
+
+    fn parameter(
+        position: i64,
+        mode: Option<&str>,
+        name: Option<&str>,
+        data_type: &str,
+    ) -> ParameterRow {
+        ParameterRow {
+            routine_name: "add_item".to_string(),
+            routine_type: if mode.is_some() {
+                "PROCEDURE"
+            } else {
+                "FUNCTION"
+            }
+            .to_string(),
+            position,
+            mode: mode.map(str::to_string),
+            name: name.map(str::to_string),
+            data_type: data_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn parameters_should_make_signatures() {
+        let procedure = [
+            parameter(1, Some("IN"), Some("order_id"), "int"),
+            parameter(2, Some("OUT"), Some("total"), "decimal(10,2)"),
+        ];
+        let procedure: Vec<_> = procedure.iter().collect();
+        assert_eq!(
+            signature("add_item", &procedure),
+            "add_item(IN order_id int, OUT total decimal(10,2))"
+        );
+
+        let function = [
+            parameter(0, None, None, "int"),
+            parameter(1, None, Some("x"), "int"),
+        ];
+        let function: Vec<_> = function.iter().collect();
+        assert_eq!(signature("double_it", &function), "double_it(x int)");
+        assert_eq!(signature("now_utc", &[]), "now_utc()");
+    }
+
+    #[test]
+    fn rows_should_group_into_keys() {
+        let indexes = group_indexes(vec![
+            index_row("PRIMARY", 0, "id", None),
+            index_row("idx_name", 1, "last", Some(10)),
+            index_row("idx_name", 1, "first", None),
+        ]);
+        assert_eq!(indexes.len(), 2);
+        assert!(indexes[0].unique);
+        assert_eq!(indexes[1].columns, vec!["last(10)", "first"]);
+
+        let keys = group_foreign_keys(vec![
+            fk_row("fk_order", "items", "order_id", "id"),
+            fk_row("fk_order", "items", "order_rev", "rev"),
+            fk_row("fk_order", "refunds", "order_id", "id"),
+        ]);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].columns, vec!["order_id", "order_rev"]);
+        assert_eq!(keys[0].referenced_columns, vec!["id", "rev"]);
+        assert_eq!(keys[1].table, "refunds");
+        assert_eq!(keys[1].on_delete, "CASCADE");
+    }
+}
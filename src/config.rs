@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Error;
+use serde::Deserialize;
+
+use crate::conn_spec::{ConnFields, ConnSpec};
+use crate::mysql::ConnOptions;
+
+/// Server configuration, loaded from a TOML or YAML file at startup.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Connections keyed by the name used as their `conn_id`.
+    #[serde(default)]
+    pub(crate) connections: BTreeMap<String, ConnConfig>,
+}
+
+/// A named connection, with the same options `register` accepts. It is
+/// given either as a `url` or field by field as `connection`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ConnConfig {
+    pub(crate) url: Option<ConnSpec>,
+    pub(crate) connection: Option<ConnFields>,
+    #[serde(flatten)]
+    pub(crate) options: ConnOptions,
+}
+
+impl Config {
+    /// Load a config, parsed as YAML for `.yaml`/`.yml` files and TOML
+    /// otherwise. Relative policy paths are resolved against its directory.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read config {}: {}", path.display(), e))?;
+        let mut config: Config = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content)?,
+            _ => toml::from_str(&content)?,
+        };
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for (name, conn) in &mut config.connections {
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("Connection names must not be empty"));
+            }
+            conn.spec()
+                .map_err(|e| anyhow::anyhow!("connections.{}: {}", name, e))?;
+            let policy_file = conn.options.policy_file.as_mut();
+            if let Some(policy_file) = policy_file.filter(|p| p.is_relative()) {
+                *policy_file = dir.join(&*policy_file);
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl ConnConfig {
+    pub(crate) fn spec(&self) -> Result<ConnSpec, Error> {
+        ConnSpec::either(self.url.clone(), self.connection.clone(), "url")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_should_resolve_policy_paths() {
+        let dir = std::env::temp_dir().join(format!("config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("mysql-mcp.toml");
+        let content = r#"
+            [connections.reporting]
+            url = "mysql://reader@db/shop"
+            read_only = true
+            max_rows = 200
+            policy_file = "reporting.toml"
+
+            [connections.scratch]
+            connection = { user = "root", password_env = "SCRATCH_PASSWORD", database = "scratch" }
+        "#;
+        std::fs::write(&path, content).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let reporting = &config.connections["reporting"];
+        assert!(reporting.options.read_only);
+        assert_eq!(reporting.options.max_rows, Some(200));
+        assert_eq!(
+            reporting.options.policy_file.as_deref(),
+            Some(dir.join("reporting.toml").as_path())
+        );
+        let scratch = &config.connections["scratch"];
+        assert!(!scratch.options.read_only);
+        assert_eq!(
+            scratch.spec().unwrap().to_string(),
+            "mysql://root@localhost/scratch"
+        );
+
+        std::fs::create_dir(&dir).unwrap();
+        let content = r#"
+            [connections.broken]
+            connection = { host = "db", port = 0 }
+        "#;
+        std::fs::write(&path, content).unwrap();
+        let err = Config::load(&path).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "connections.broken: port: must be between 1 and 65535"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use serde_json::Value;
+use sqlx::MySql;
+use sqlx::mysql::MySqlPool;
+use sqlx::pool::PoolConnection;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use crate::deadline::{kill_query, reset_max_execution_time};
+use crate::mask::MaskPlan;
+use crate::result_set::{ColumnMeta, Page, RowLimits, decode_row};
+use crate::value::bind_params;
+
+/// How long an untouched cursor is kept before its connection is released.
+pub(crate) const CURSOR_TTL: Duration = Duration::from_secs(300);
+/// Each open cursor pins a pooled connection, so keep the number bounded.
+pub(crate) const MAX_CURSORS_PER_CONN: usize = 4;
+/// Rows decoded ahead of the caller while a page is being consumed.
+const PREFETCH_ROWS: usize = 64;
+
+type RowResult = Result<Vec<Value>, String>;
+
+type Slots = Arc<Mutex<HashMap<String, Cursor>>>;
+
+/// Open cursors of a single connection, keyed by cursor id.
+#[derive(Debug, Clone)]
+pub(crate) struct Cursors {
+    inner: Slots,
+    ttl: Duration,
+}
+
+/// A query result being read page by page. The rows are streamed by a
+/// background task that owns a pooled connection for the cursor's lifetime.
+/// Dropping a cursor before its query finished kills the query.
+#[derive(Debug)]
+pub(crate) struct Cursor {
+    /// Server side id of the connection streaming the rows.
+    pub(crate) connection_id: u64,
+    /// To kill the query from a side connection.
+    pool: MySqlPool,
+    pub(crate) columns: Vec<ColumnMeta>,
+    pub(crate) masks: MaskPlan,
+    limits: RowLimits,
+    rx: mpsc::Receiver<RowResult>,
+    /// Row read ahead of the current page.
+    pending: Option<RowResult>,
+    expires_at: Instant,
+    task: JoinHandle<()>,
+}
+
+/// Pooled connection that is closed instead of returned to the pool when the
+/// result was not read to the end, so the pool never has to drain it.
+struct StreamConn {
+    db: PoolConnection<MySql>,
+    exhausted: bool,
+}
+
+impl Default for Cursors {
+    fn default() -> Self {
+        Self::new(CURSOR_TTL)
+    }
+}
+
+impl Cursors {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Default::default(),
+            ttl,
+        }
+    }
+
+    /// Store `cursor` and return its id.
+    pub(crate) fn insert(&self, mut cursor: Cursor) -> Result<String, Error> {
+        let mut cursors = self.inner.lock().unwrap();
+        let now = Instant::now();
+        cursors.retain(|_, c| c.expires_at > now);
+        if cursors.len() >= MAX_CURSORS_PER_CONN {
+            return Err(anyhow::anyhow!(
+                "Too many open cursors, read them to the end or wait for them to expire"
+            ));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        cursor.expires_at = now + self.ttl;
+        tokio::spawn(watch(
+            Arc::downgrade(&self.inner),
+            id.clone(),
+            cursor.expires_at,
+        ));
+        cursors.insert(id.clone(), cursor);
+        Ok(id)
+    }
+
+    /// Take a cursor out of the set while a page is read from it.
+    pub(crate) fn take(&self, id: &str) -> Result<Cursor, Error> {
+        let mut cursors = self.inner.lock().unwrap();
+        let now = Instant::now();
+        cursors.retain(|_, c| c.expires_at > now);
+        cursors
+            .remove(id)
+            .ok_or_else(|| anyhow::anyhow!("Cursor not found or expired"))
+    }
+
+    /// Put a cursor back under its id, refreshing its expiry.
+    pub(crate) fn restore(&self, id: String, mut cursor: Cursor) {
+        cursor.expires_at = Instant::now() + self.ttl;
+        tokio::spawn(watch(
+            Arc::downgrade(&self.inner),
+            id.clone(),
+            cursor.expires_at,
+        ));
+        self.inner.lock().unwrap().insert(id, cursor);
+    }
+
+    /// Drop every cursor, releasing their connections.
+    pub(crate) fn clear(&self) {
+        self.inner.lock().unwrap().clear();
+    }
+}
+
+/// Drop the cursor `id` once it expires, so an abandoned cursor doesn't pin
+/// its connection until the next cursor call. Every insert or restore starts
+/// a watcher, the ones outdated by a later restore just return.
+async fn watch(cursors: Weak<Mutex<HashMap<String, Cursor>>>, id: String, expires_at: Instant) {
+    tokio::time::sleep_until(expires_at.into()).await;
+    let Some(cursors) = cursors.upgrade() else {
+        return;
+    };
+    let mut cursors = cursors.lock().unwrap();
+    // taken out to read a page, or used again while we slept
+    if cursors
+        .get(&id)
+        .is_none_or(|cursor| cursor.expires_at > Instant::now())
+    {
+        return;
+    }
+    let cursor = cursors.remove(&id);
+    drop(cursors);
+    tracing::info!(cursor_id = id, "dropping expired cursor");
+    drop(cursor);
+}
+
+impl Cursor {
+    /// Start streaming `query` on `db` in the background.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn open(
+        pool: MySqlPool,
+        db: PoolConnection<MySql>,
+        connection_id: u64,
+        query: String,
+        params: Vec<Value>,
+        columns: Vec<ColumnMeta>,
+        masks: MaskPlan,
+        limits: RowLimits,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(PREFETCH_ROWS);
+        let task = tokio::spawn(async move {
+            let mut conn = StreamConn {
+                db,
+                exhausted: false,
+            };
+            let mut stream = bind_params(sqlx::query(&query), &params).fetch(&mut *conn.db);
+            let mut exhausted = true;
+            while let Some(row) = stream.next().await {
+                let row = row.map(|row| decode_row(&row)).map_err(|e| e.to_string());
+                let failed = row.is_err();
+                if tx.send(row).await.is_err() {
+                    exhausted = false;
+                    break;
+                }
+                if failed {
+                    exhausted = false;
+                    break;
+                }
+            }
+            drop(stream);
+            // the cursor ran without a time limit, which must not stay with
+            // the connection
+            conn.exhausted = exhausted && reset_max_execution_time(&mut conn.db).await.is_ok();
+        });
+
+        Self {
+            connection_id,
+            pool,
+            columns,
+            masks,
+            limits,
+            rx,
+            pending: None,
+            expires_at: Instant::now() + CURSOR_TTL,
+            task,
+        }
+    }
+
+    /// Read the next page. Rows left over are served by the following call;
+    /// once [`Cursor::is_exhausted`] is true there is nothing left to read.
+    pub(crate) async fn next_page(&mut self) -> Result<Page, Error> {
+        let mut page = Page::default();
+        let mut bytes = 0;
+
+        while page.rows.len() < self.limits.max_rows {
+            let Some(row) = self.next_row().await else {
+                break;
+            };
+            let row = row.map_err(|e| anyhow::anyhow!(e))?;
+
+            bytes += serde_json::to_vec(&row)?.len();
+            if bytes > self.limits.max_bytes && !page.rows.is_empty() {
+                // counted by the page that returns it
+                self.pending = Some(Ok(row));
+                break;
+            }
+            page.rows_seen += 1;
+            page.rows.push(row);
+        }
+
+        // look one row ahead so the caller knows whether another page exists
+        if self.pending.is_none() {
+            self.pending = self.rx.recv().await;
+        }
+
+        Ok(page)
+    }
+
+    /// Whether every row has been handed out.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    async fn next_row(&mut self) -> Option<RowResult> {
+        match self.pending.take() {
+            Some(row) => Some(row),
+            None => self.rx.recv().await,
+        }
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        let finished = self.task.is_finished();
+        self.task.abort();
+        if finished {
+            return;
+        }
+        // the query runs without a server side time limit, closing the
+        // connection alone leaves it running until it next sends a row
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = self.pool.clone();
+        let connection_id = self.connection_id;
+        runtime.spawn(async move {
+            if let Err(e) = kill_query(&pool, connection_id).await {
+                tracing::warn!(connection_id, error = %e, "failed to kill cursor query");
+            }
+        });
+    }
+}
+
+impl Drop for StreamConn {
+    fn drop(&mut self) {
+        if !self.exhausted {
+            self.db.close_on_drop();
+        }
+    }
+}
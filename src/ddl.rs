@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Error;
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlRow};
+use sqlx::{Executor, Row};
+
+use crate::ident::QualifiedName;
+use crate::policy::Policy;
+
+/// Kinds of objects `get_ddl` can show the definition of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectKind {
+    Table,
+    View,
+    Procedure,
+    Function,
+    Trigger,
+    Event,
+}
+
+impl ObjectKind {
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::Table => "TABLE",
+            Self::View => "VIEW",
+            Self::Procedure => "PROCEDURE",
+            Self::Function => "FUNCTION",
+            Self::Trigger => "TRIGGER",
+            Self::Event => "EVENT",
+        }
+    }
+
+    /// The column of the `SHOW CREATE` result holding the definition.
+    fn column(self) -> &'static str {
+        match self {
+            Self::Table => "Create Table",
+            Self::View => "Create View",
+            Self::Procedure => "Create Procedure",
+            Self::Function => "Create Function",
+            Self::Trigger => "SQL Original Statement",
+            Self::Event => "Create Event",
+        }
+    }
+}
+
+/// The `SHOW CREATE` output for a single object, as the server prints it.
+pub(crate) async fn show_create(
+    pool: &MySqlPool,
+    kind: ObjectKind,
+    name: &QualifiedName,
+) -> Result<String, Error> {
+    let query = format!("SHOW CREATE {} {}", kind.keyword(), name);
+    // SHOW CREATE TRIGGER and EVENT can't be prepared, so all of them go
+    // over the text protocol
+    let row = pool.fetch_one(query.as_str()).await?;
+    // a view asked for as a table is shown as the view it is
+    let column = match kind {
+        ObjectKind::Table if row.try_column("Create View").is_ok() => ObjectKind::View.column(),
+        _ => kind.column(),
+    };
+    text(&row, column)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "The definition of {} {} is hidden, the user lacks the privileges to see it",
+            kind.keyword(),
+            name
+        )
+    })
+}
+
+/// The definitions of every object in `schema` that `policy` allows, in an
+/// order they can be created in: tables before the tables referencing them,
+/// then functions and procedures, views after the views they select from,
+/// and finally triggers and events. Tables in a foreign key cycle come last
+/// among the tables and need `FOREIGN_KEY_CHECKS = 0` to be loaded.
+pub(crate) async fn schema_ddl(
+    pool: &MySqlPool,
+    schema: &str,
+    policy: Option<&Policy>,
+) -> Result<String, Error> {
+    let allowed = |name: &str| {
+        policy.is_none_or(|policy| {
+            policy
+                .check_table(&QualifiedName {
+                    schema: Some(schema.to_string()),
+                    name: name.to_string(),
+                })
+                .is_ok()
+        })
+    };
+
+    let query = r#"
+      SELECT TABLE_NAME, TABLE_TYPE FROM information_schema.tables
+      WHERE TABLE_SCHEMA = ? AND TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+      ORDER BY TABLE_NAME
+    "#;
+    let mut tables = Vec::new();
+    let mut views = Vec::new();
+    for (name, table_type) in sqlx::query_as::<_, (String, String)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?
+    {
+        if !allowed(&name) {
+            continue;
+        }
+        match table_type.as_str() {
+            "VIEW" => views.push(name),
+            _ => tables.push(name),
+        }
+    }
+
+    let query = r#"
+      SELECT DISTINCT TABLE_NAME, REFERENCED_TABLE_NAME
+      FROM information_schema.referential_constraints
+      WHERE CONSTRAINT_SCHEMA = ? AND UNIQUE_CONSTRAINT_SCHEMA = CONSTRAINT_SCHEMA
+    "#;
+    let references = sqlx::query_as::<_, (String, String)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+    let query = r#"
+      SELECT VIEW_NAME, TABLE_NAME FROM information_schema.view_table_usage
+      WHERE VIEW_SCHEMA = ? AND TABLE_SCHEMA = VIEW_SCHEMA
+    "#;
+    let view_usage = match sqlx::query_as::<_, (String, String)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+    {
+        // before MySQL 8.0.13 and on MariaDB there is no view_table_usage
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42S02") => Vec::new(),
+        result => result?,
+    };
+
+    let mut objects: Vec<(ObjectKind, String)> = Vec::new();
+    objects.extend(
+        creation_order(tables, &references)
+            .into_iter()
+            .map(|name| (ObjectKind::Table, name)),
+    );
+    let query = r#"
+      SELECT ROUTINE_NAME, ROUTINE_TYPE FROM information_schema.routines
+      WHERE ROUTINE_SCHEMA = ?
+      ORDER BY ROUTINE_TYPE = 'PROCEDURE', ROUTINE_NAME
+    "#;
+    for (name, routine_type) in sqlx::query_as::<_, (String, String)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?
+    {
+        let kind = match routine_type.as_str() {
+            "FUNCTION" => ObjectKind::Function,
+            _ => ObjectKind::Procedure,
+        };
+        objects.push((kind, name));
+    }
+    objects.extend(
+        creation_order(views, &view_usage)
+            .into_iter()
+            .map(|name| (ObjectKind::View, name)),
+    );
+    let query = r#"
+      SELECT TRIGGER_NAME, EVENT_OBJECT_TABLE FROM information_schema.triggers
+      WHERE TRIGGER_SCHEMA = ?
+      ORDER BY EVENT_OBJECT_TABLE, ACTION_TIMING, EVENT_MANIPULATION, ACTION_ORDER
+    "#;
+    for (name, table) in sqlx::query_as::<_, (String, String)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?
+    {
+        if allowed(&table) {
+            objects.push((ObjectKind::Trigger, name));
+        }
+    }
+    let query = r#"
+      SELECT EVENT_NAME FROM information_schema.events
+      WHERE EVENT_SCHEMA = ?
+      ORDER BY EVENT_NAME
+    "#;
+    for name in sqlx::query_scalar::<_, String>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?
+    {
+        objects.push((ObjectKind::Event, name));
+    }
+
+    let mut ddl = String::new();
+    for (kind, name) in objects {
+        let name = QualifiedName {
+            schema: Some(schema.to_string()),
+            name,
+        };
+        ddl.push_str(&show_create(pool, kind, &name).await?);
+        ddl.push_str(";\n\n");
+    }
+    Ok(ddl)
+}
+
+/// Sort `names` so every name comes after the ones it depends on, given
+/// `(dependent, dependency)` pairs, keeping the input order where the
+/// dependencies leave a choice. Names in a cycle are appended in input order.
+fn creation_order(names: Vec<String>, dependencies: &[(String, String)]) -> Vec<String> {
+    let known: BTreeSet<&str> = names.iter().map(String::as_str).collect();
+    let mut pending: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (dependent, dependency) in dependencies {
+        // self references and objects outside `names` don't hold anything up
+        if dependent != dependency && known.contains(dependency.as_str()) {
+            pending
+                .entry(dependent.as_str())
+                .or_default()
+                .insert(dependency.as_str());
+        }
+    }
+
+    let mut ordered: Vec<String> = Vec::with_capacity(names.len());
+    let mut remaining = names.clone();
+    loop {
+        let ready: Vec<String> = remaining
+            .iter()
+            .filter(|name| {
+                pending.get(name.as_str()).is_none_or(|dependencies| {
+                    dependencies
+                        .iter()
+                        .all(|dependency| ordered.iter().any(|done| done == dependency))
+                })
+            })
+            .cloned()
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        remaining.retain(|name| !ready.contains(name));
+        ordered.extend(ready);
+    }
+    ordered.extend(remaining);
+    ordered
+}
+
+/// A text column, which servers may send as binary for some `SHOW` output.
+fn text(row: &MySqlRow, column: &str) -> Result<Option<String>, Error> {
+    match row.try_get::<Option<String>, _>(column) {
+        Ok(value) => Ok(value),
+        Err(_) => Ok(row
+            .try_get::<Option<Vec<u8>>, _>(column)?
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn edges(edges: &[(&str, &str)]) -> Vec<(String, String)> {
+        edges
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn creation_order_should_put_dependencies_first() {
+        let order = creation_order(
+            names(&["items", "orders", "products", "users"]),
+            &edges(&[
+                ("items", "orders"),
+                ("items", "products"),
+                ("orders", "users"),
+                ("users", "users"),
+                ("orders", "elsewhere"),
+            ]),
+        );
+        assert_eq!(order, names(&["products", "users", "orders", "items"]));
+
+        let order = creation_order(names(&["a", "b", "c"]), &edges(&[("a", "b"), ("b", "a")]));
+        assert_eq!(order, names(&["c", "a", "b"]));
+    }
+}
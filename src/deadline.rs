@@ -0,0 +1,80 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Error;
+use sqlx::mysql::{MySqlConnection, MySqlPool};
+use sqlx::{ConnectOptions, Executor};
+use tokio_util::sync::CancellationToken;
+
+/// Statement timeout when neither the connection nor the call sets one.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a killed statement gets to unwind before it is abandoned.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// Bounds a single tool call: a time limit plus the MCP request's
+/// cancellation token.
+#[derive(Debug, Clone)]
+pub(crate) struct Deadline {
+    pub(crate) timeout: Duration,
+    pub(crate) ct: CancellationToken,
+}
+
+impl Deadline {
+    /// Server side limit for SELECT statements, in milliseconds.
+    pub(crate) fn max_execution_time(&self) -> u64 {
+        self.timeout.as_millis().try_into().unwrap_or(u64::MAX)
+    }
+
+    /// Await `fut`, which runs on the server connection `connection_id`. If the
+    /// timeout passes or the call is cancelled first, the running statement is
+    /// stopped with `KILL QUERY` from a side connection.
+    pub(crate) async fn run<T>(
+        &self,
+        pool: &MySqlPool,
+        connection_id: u64,
+        fut: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        tokio::pin!(fut);
+
+        let error = tokio::select! {
+            result = &mut fut => return result,
+            _ = tokio::time::sleep(self.timeout) => {
+                anyhow::anyhow!("Query timed out after {}ms", self.timeout.as_millis())
+            }
+            _ = self.ct.cancelled() => anyhow::anyhow!("Query cancelled"),
+        };
+
+        if let Err(e) = kill_query(pool, connection_id).await {
+            tracing::warn!(connection_id, error = %e, "failed to kill query");
+        }
+        // let the statement unwind so the connection is left in a clean state
+        let _ = tokio::time::timeout(KILL_GRACE, fut).await;
+
+        Err(error)
+    }
+}
+
+/// Server side id of `conn`, as used by `KILL`.
+pub(crate) async fn connection_id(conn: &mut MySqlConnection) -> Result<u64, Error> {
+    Ok(sqlx::query_scalar("SELECT CONNECTION_ID()")
+        .fetch_one(conn)
+        .await?)
+}
+
+/// Put the time limit of SELECT statements back to the server default, so a
+/// per-call limit doesn't stay with a pooled connection.
+pub(crate) async fn reset_max_execution_time(conn: &mut MySqlConnection) -> Result<(), Error> {
+    conn.execute("SET SESSION max_execution_time = DEFAULT")
+        .await?;
+    Ok(())
+}
+
+/// Stop the statement running on `connection_id`. This opens a fresh
+/// connection since the pool may be exhausted by the very query being killed.
+pub(crate) async fn kill_query(pool: &MySqlPool, connection_id: u64) -> Result<(), Error> {
+    let mut conn = pool.connect_options().connect().await?;
+    conn.execute(format!("KILL QUERY {}", connection_id).as_str())
+        .await?;
+    sqlx::Connection::close(conn).await?;
+    Ok(())
+}
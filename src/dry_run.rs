@@ -0,0 +1,250 @@
+use anyhow::Error;
+use serde::Serialize;
+use serde_json::Value;
+use sqlparser::ast::{FromTable, Statement, TableWithJoins};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Location, Token, Tokenizer};
+
+use crate::result_set::ResultSet;
+use crate::value::count_placeholders;
+
+/// Rows of the affected set shown by a dry run.
+pub(crate) const SAMPLE_ROWS: usize = 10;
+
+/// Outcome of an UPDATE or DELETE that was executed and rolled back.
+#[derive(Debug, Serialize)]
+pub(crate) struct DryRun {
+    pub(crate) dry_run: bool,
+    pub(crate) rows_affected: u64,
+    /// Rows matched by the statement, as they were before it ran.
+    pub(crate) sample: ResultSet,
+}
+
+/// Split the `ORDER BY` and `LIMIT` off a MySQL UPDATE, as the parser doesn't
+/// read them. Returns the rest of the UPDATE and the split off clauses, which
+/// [`sample_query`] carries over. Keywords in parentheses belong to a
+/// subquery and stay.
+pub(crate) fn split_update_tail(query: &str) -> Result<(&str, Option<&str>), Error> {
+    let tokens = Tokenizer::new(&MySqlDialect {}, query).tokenize_with_location()?;
+    let mut tokens = tokens
+        .iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .peekable();
+    let mut depth = 0usize;
+    while let Some(token) = tokens.next() {
+        let tail = match &token.token {
+            Token::LParen => {
+                depth += 1;
+                false
+            }
+            Token::RParen => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            Token::Word(word) if depth == 0 && word.quote_style.is_none() => match word.keyword {
+                Keyword::LIMIT => true,
+                Keyword::ORDER => tokens.peek().is_some_and(
+                    |next| matches!(&next.token, Token::Word(word) if word.keyword == Keyword::BY),
+                ),
+                _ => false,
+            },
+            _ => false,
+        };
+        if tail {
+            let (head, tail) = query.split_at(offset(query, token.span.start));
+            return Ok((head.trim_end(), Some(tail)));
+        }
+    }
+    Ok((query, None))
+}
+
+/// Byte offset of `location`, as reported by the tokenizer, in `sql`.
+fn offset(sql: &str, location: Location) -> usize {
+    let line: usize = sql
+        .split_inclusive('\n')
+        .take(location.line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum();
+    let column: usize = sql[line..]
+        .chars()
+        .take(location.column.saturating_sub(1) as usize)
+        .map(char::len_utf8)
+        .sum();
+    line + column
+}
+
+/// Rewrite an UPDATE or DELETE into a SELECT over the rows it matches.
+/// `tail` is the `ORDER BY` and `LIMIT` split off an UPDATE with
+/// [`split_update_tail`]. Returns the query together with the subset of
+/// `params` it references.
+pub(crate) fn sample_query(
+    statement: &Statement,
+    tail: Option<&str>,
+    params: &[Value],
+) -> Result<(String, Vec<Value>), Error> {
+    match statement {
+        Statement::Update {
+            table,
+            assignments,
+            from,
+            selection,
+            ..
+        } => {
+            if from.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Cannot derive the affected rows of UPDATE ... FROM"
+                ));
+            }
+
+            let mut sql = format!("SELECT * FROM {}", table);
+            if let Some(selection) = selection {
+                sql.push_str(&format!(" WHERE {}", selection));
+            }
+            // only the rows the UPDATE reaches, in the order it does
+            if let Some(tail) = tail {
+                sql.push_str(&format!(" {}", tail));
+            }
+
+            // the SET list sits between the tables and the WHERE clause, its
+            // params have no place in the SELECT
+            let before = count_placeholders(&table.to_string())?;
+            let assignments = comma_separated(assignments);
+            let skipped = count_placeholders(&assignments)?;
+            let params = params[..before]
+                .iter()
+                .chain(&params[before + skipped..])
+                .cloned()
+                .collect();
+            Ok((sql, params))
+        }
+        Statement::Delete(delete) => {
+            let from = match &delete.from {
+                FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => from,
+            };
+            // `DELETE FROM t1 USING t1 JOIN t2` names the targets in FROM,
+            // `DELETE t1 FROM t1 JOIN t2` names them before it
+            let (targets, sources): (Vec<String>, &[TableWithJoins]) = match &delete.using {
+                Some(using) => (from.iter().map(ToString::to_string).collect(), using),
+                None => (
+                    delete.tables.iter().map(ToString::to_string).collect(),
+                    from,
+                ),
+            };
+            let projection = if targets.is_empty() {
+                "*".to_string()
+            } else {
+                targets
+                    .iter()
+                    .map(|t| format!("{}.*", t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+
+            let mut sql = format!("SELECT {} FROM {}", projection, comma_separated(sources));
+            if let Some(selection) = &delete.selection {
+                sql.push_str(&format!(" WHERE {}", selection));
+            }
+            if !delete.order_by.is_empty() {
+                sql.push_str(&format!(" ORDER BY {}", comma_separated(&delete.order_by)));
+            }
+            if let Some(limit) = &delete.limit {
+                sql.push_str(&format!(" LIMIT {}", limit));
+            }
+            Ok((sql, params.to_vec()))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Dry run is only supported for UPDATE and DELETE"
+        )),
+    }
+}
+
+fn comma_separated<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    use super::*;
+
+    fn sample(sql: &str, params: &[Value]) -> (String, Vec<Value>) {
+        let (sql, tail) = match sql.starts_with("UPDATE") {
+            true => split_update_tail(sql).unwrap(),
+            false => (sql, None),
+        };
+        let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        sample_query(&statement, tail, params).unwrap()
+    }
+
+    #[test]
+    fn update_should_keep_where_params() {
+        let (sql, params) = sample(
+            "UPDATE users SET name = ?, age = ? WHERE id = ?",
+            &[json!("a"), json!(1), json!(7)],
+        );
+        assert_eq!(sql, "SELECT * FROM users WHERE id = ?");
+        assert_eq!(params, vec![json!(7)]);
+
+        let (sql, params) = sample("UPDATE users SET active = 0", &[]);
+        assert_eq!(sql, "SELECT * FROM users");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn update_should_keep_order_by_and_limit() {
+        let (sql, params) = sample(
+            "UPDATE users SET name = ? WHERE age > ?\nORDER BY created_at DESC LIMIT ?",
+            &[json!("a"), json!(18), json!(5)],
+        );
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE age > ? ORDER BY created_at DESC LIMIT ?"
+        );
+        assert_eq!(params, vec![json!(18), json!(5)]);
+
+        let (sql, _) = sample(
+            "UPDATE users SET note = 'ORDER BY' WHERE id IN (SELECT id FROM t ORDER BY id LIMIT 3) LIMIT 1",
+            &[],
+        );
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE id IN (SELECT id FROM t ORDER BY id LIMIT 3) LIMIT 1"
+        );
+
+        let query = "UPDATE users SET `order` = 1 WHERE é = 'x' ORDER BY id";
+        let (head, tail) = split_update_tail(query).unwrap();
+        assert_eq!(head, "UPDATE users SET `order` = 1 WHERE é = 'x'");
+        assert_eq!(tail, Some("ORDER BY id"));
+        assert_eq!(split_update_tail("UPDATE users SET a = 1").unwrap().1, None);
+    }
+
+    #[test]
+    fn delete_should_select_targets() {
+        let (sql, params) = sample(
+            "DELETE FROM orders WHERE total > ? ORDER BY id LIMIT 5",
+            &[json!(10)],
+        );
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE total > ? ORDER BY id LIMIT 5"
+        );
+        assert_eq!(params, vec![json!(10)]);
+
+        let (sql, _) = sample(
+            "DELETE o FROM orders o JOIN users u ON o.user_id = u.id WHERE u.active = 0",
+            &[],
+        );
+        assert_eq!(
+            sql,
+            "SELECT o.* FROM orders AS o JOIN users AS u ON o.user_id = u.id WHERE u.active = 0"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Error;
+use sqlparser::ast::{BinaryOperator, Expr, Statement, UnaryOperator};
+
+use crate::policy::statement_kind;
+
+/// Whether `statement` only reads: a query, SHOW, DESCRIBE, or EXPLAIN of one
+/// of those.
+pub(crate) fn is_read_only(statement: &Statement) -> bool {
+    match statement {
+        Statement::Explain { statement, .. } => is_read_only(statement),
+        _ => matches!(statement_kind(statement), "select" | "show" | "describe"),
+    }
+}
+
+/// Reject anything but reads on a read-only connection.
+pub(crate) fn check_read_only(statement: &Statement) -> Result<(), Error> {
+    if !is_read_only(statement) {
+        return Err(read_only_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn read_only_error() -> Error {
+    anyhow::anyhow!("Connection is read-only, only SELECT, SHOW, EXPLAIN and DESCRIBE are allowed")
+}
+
+/// Reject an UPDATE or DELETE whose WHERE clause is missing or always true,
+/// unless the caller explicitly allows touching every row.
+pub(crate) fn check_predicate(statement: &Statement, allow_full_table: bool) -> Result<(), Error> {
+    if allow_full_table {
+        return Ok(());
+    }
+
+    let (kind, selection) = match statement {
+        Statement::Update { selection, .. } => ("UPDATE", selection.as_ref()),
+        Statement::Delete(delete) => ("DELETE", delete.selection.as_ref()),
+        _ => return Ok(()),
+    };
+    match selection {
+        None => Err(anyhow::anyhow!(
+            "{} without a WHERE clause affects every row, set allow_full_table to run it anyway",
+            kind
+        )),
+        Some(expr) if constant_truth(expr) == Some(true) => Err(anyhow::anyhow!(
+            "{} with WHERE {} affects every row, set allow_full_table to run it anyway",
+            kind,
+            expr
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Truth value of a predicate that does not depend on the row, e.g.
+/// `1 = 1`, `TRUE` or `id = id`. `None` when it can't be decided statically.
+fn constant_truth(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Nested(expr) => constant_truth(expr),
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => constant_truth(expr).map(|truth| !truth),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => match (constant_truth(left), constant_truth(right)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Or,
+            right,
+        } => match (constant_truth(left), constant_truth(right)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::BinaryOp { left, op, right } => compare(left, op, right),
+        _ => number(expr).map(|n| n != 0.0),
+    }
+}
+
+fn compare(left: &Expr, op: &BinaryOperator, right: &Expr) -> Option<bool> {
+    let ordering = match (number(left), number(right)) {
+        (Some(l), Some(r)) => l.partial_cmp(&r)?,
+        // a column or literal compared with itself, `RAND() = RAND()` is not
+        _ if is_plain(left) && left.to_string() == right.to_string() => std::cmp::Ordering::Equal,
+        _ => return None,
+    };
+    match op {
+        BinaryOperator::Eq | BinaryOperator::Spaceship => Some(ordering.is_eq()),
+        BinaryOperator::NotEq => Some(ordering.is_ne()),
+        BinaryOperator::Lt => Some(ordering.is_lt()),
+        BinaryOperator::LtEq => Some(ordering.is_le()),
+        BinaryOperator::Gt => Some(ordering.is_gt()),
+        BinaryOperator::GtEq => Some(ordering.is_ge()),
+        _ => None,
+    }
+}
+
+/// Value of a numeric or boolean literal.
+fn number(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Nested(expr) => number(expr),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => number(expr).map(|n| -n),
+        Expr::UnaryOp {
+            op: UnaryOperator::Plus,
+            expr,
+        } => number(expr),
+        Expr::Value(_) => match expr.to_string().to_uppercase().as_str() {
+            "TRUE" => Some(1.0),
+            "FALSE" => Some(0.0),
+            literal => literal.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn is_plain(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) | Expr::Value(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    use super::*;
+
+    fn check(sql: &str) -> Result<(), Error> {
+        let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        check_predicate(&statement, false)
+    }
+
+    #[test]
+    fn predicate_should_restrict_rows() {
+        assert!(check("UPDATE t SET a = 1 WHERE id = 1").is_ok());
+        assert!(check("DELETE FROM t WHERE id = ? OR 1 = 0").is_ok());
+        assert!(check("DELETE FROM t WHERE 1 = 1 AND id = 5").is_ok());
+        assert!(check("DELETE FROM t WHERE NOT (1 = 1)").is_ok());
+        assert!(check("DELETE FROM t WHERE RAND() = RAND()").is_ok());
+
+        assert!(check("UPDATE t SET a = 1").is_err());
+        assert!(check("DELETE FROM t").is_err());
+        assert!(check("DELETE FROM t WHERE 1=1").is_err());
+        assert!(check("DELETE FROM t WHERE TRUE").is_err());
+        assert!(check("DELETE FROM t WHERE (1)").is_err());
+        assert!(check("UPDATE t SET a = 1 WHERE id = id").is_err());
+        assert!(check("UPDATE t SET a = 1 WHERE 'x' = 'x'").is_err());
+        assert!(check("UPDATE t SET a = 1 WHERE id = 3 OR 2 > 1").is_err());
+    }
+
+    #[test]
+    fn read_only_should_allow_reads() {
+        let parse = |sql| Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        assert!(is_read_only(&parse("SELECT * FROM t")));
+        assert!(is_read_only(&parse("SHOW TABLES")));
+        assert!(is_read_only(&parse("DESCRIBE t")));
+        assert!(is_read_only(&parse("EXPLAIN SELECT * FROM t")));
+
+        assert!(!is_read_only(&parse("EXPLAIN DELETE FROM t WHERE id = 1")));
+        assert!(!is_read_only(&parse("INSERT INTO t VALUES (1)")));
+        assert!(!is_read_only(&parse("DROP TABLE t")));
+        assert!(check_read_only(&parse("TRUNCATE TABLE t")).is_err());
+    }
+
+    #[test]
+    fn override_should_allow_full_table() {
+        let sql = "DELETE FROM t";
+        let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        assert!(check_predicate(&statement, true).is_ok());
+    }
+}
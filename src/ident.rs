@@ -0,0 +1,243 @@
+use std::fmt;
+
+use anyhow::Error;
+use sqlx::mysql::MySqlPool;
+
+/// Longest identifier MySQL accepts for schemas, tables and indexes.
+const MAX_IDENT_LEN: usize = 64;
+
+/// A table name as given to a tool, e.g. `orders`, `shop.orders` or
+/// `` `my.db`.`odd``name` ``.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QualifiedName {
+    /// `None` means the connection's current schema.
+    pub(crate) schema: Option<String>,
+    pub(crate) name: String,
+}
+
+impl QualifiedName {
+    pub(crate) fn parse(input: &str) -> Result<Self, Error> {
+        let mut parts = parse_parts(input)?;
+        match parts.len() {
+            1 => Ok(Self {
+                schema: None,
+                name: parts.remove(0),
+            }),
+            2 => Ok(Self {
+                name: parts.remove(1),
+                schema: Some(parts.remove(0)),
+            }),
+            _ => Err(anyhow::anyhow!(
+                "Invalid name {}, expected table or schema.table",
+                input
+            )),
+        }
+    }
+}
+
+/// Formats as a quoted identifier that is safe to splice into SQL.
+impl fmt::Display for QualifiedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = &self.schema {
+            write!(f, "{}.", quote(schema))?;
+        }
+        write!(f, "{}", quote(&self.name))
+    }
+}
+
+/// Parse a single, unqualified identifier such as a schema or index name.
+pub(crate) fn parse_ident(input: &str) -> Result<String, Error> {
+    let mut parts = parse_parts(input)?;
+    if parts.len() != 1 {
+        return Err(anyhow::anyhow!(
+            "Invalid name {}, quote names containing dots with backticks",
+            input
+        ));
+    }
+    Ok(parts.remove(0))
+}
+
+/// Quote `ident` with backticks, doubling any backtick inside it.
+pub(crate) fn quote(ident: &str) -> String {
+    format!("`{}`", ident.replace('`', "``"))
+}
+
+/// Split a possibly qualified, possibly backtick-quoted name into its parts.
+/// Meant for names printed by sqlparser, which are always well formed.
+pub(crate) fn split_name(name: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut chars = name.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '`' if quoted && chars.peek() == Some(&'`') => {
+                current.push('`');
+                chars.next();
+            }
+            '`' => quoted = !quoted,
+            '.' if !quoted => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// Like [`split_name`], but rejects malformed input: unterminated quotes,
+/// empty or overlong parts, and unquoted characters MySQL would not accept.
+fn parse_parts(input: &str) -> Result<Vec<String>, Error> {
+    let mut parts = Vec::new();
+    let mut chars = input.trim().chars().peekable();
+    loop {
+        let mut part = String::new();
+        if chars.peek() == Some(&'`') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('`') if chars.peek() == Some(&'`') => {
+                        part.push('`');
+                        chars.next();
+                    }
+                    Some('`') => break,
+                    Some(c) => part.push(c),
+                    None => return Err(anyhow::anyhow!("Unterminated quote in name {}", input)),
+                }
+            }
+            if !matches!(chars.peek(), None | Some('.')) {
+                return Err(anyhow::anyhow!("Invalid name {}", input));
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == '.' {
+                    break;
+                }
+                if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                    return Err(anyhow::anyhow!(
+                        "Invalid character {:?} in name {}, quote it with backticks",
+                        c,
+                        input
+                    ));
+                }
+                part.push(c);
+                chars.next();
+            }
+        }
+
+        if part.is_empty() {
+            return Err(anyhow::anyhow!("Empty identifier in name {:?}", input));
+        }
+        if part.chars().count() > MAX_IDENT_LEN {
+            return Err(anyhow::anyhow!(
+                "Identifier {} is longer than {} characters",
+                part,
+                MAX_IDENT_LEN
+            ));
+        }
+        parts.push(part);
+
+        match chars.next() {
+            Some('.') => continue,
+            _ => return Ok(parts),
+        }
+    }
+}
+
+/// The connection's current database, for tools whose schema is optional.
+pub(crate) async fn current_schema(pool: &MySqlPool) -> Result<String, Error> {
+    let schema: Option<String> = sqlx::query_scalar("SELECT DATABASE()")
+        .fetch_one(pool)
+        .await?;
+    schema.ok_or_else(|| {
+        anyhow::anyhow!("No schema given and the connection has no default database")
+    })
+}
+
+/// Fail unless `table` exists as a base table. A view of that name is
+/// reported as one, as the statements run on tables don't apply to it.
+pub(crate) async fn check_table(pool: &MySqlPool, table: &QualifiedName) -> Result<(), Error> {
+    let query = r#"
+      SELECT CAST(TABLE_TYPE AS CHAR) FROM information_schema.tables
+      WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+    "#;
+    let table_type: Option<String> = sqlx::query_scalar(query)
+        .bind(&table.schema)
+        .bind(&table.name)
+        .fetch_optional(pool)
+        .await?;
+    match table_type.as_deref() {
+        None => Err(anyhow::anyhow!("Table {} does not exist", table)),
+        Some("BASE TABLE") => Ok(()),
+        Some(_) => Err(anyhow::anyhow!("{} is a view, not a table", table)),
+    }
+}
+
+/// Fail unless `table` has an index named `index`.
+pub(crate) async fn check_index(
+    pool: &MySqlPool,
+    table: &QualifiedName,
+    index: &str,
+) -> Result<(), Error> {
+    check_table(pool, table).await?;
+    let query = r#"
+      SELECT COUNT(*) FROM information_schema.statistics
+      WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ? AND index_name = ?
+    "#;
+    let count: i64 = sqlx::query_scalar(query)
+        .bind(&table.schema)
+        .bind(&table.name)
+        .bind(index)
+        .fetch_one(pool)
+        .await?;
+    if count == 0 {
+        return Err(anyhow::anyhow!(
+            "Index {} does not exist on {}",
+            quote(index),
+            table
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_name_should_handle_quotes() {
+        assert_eq!(split_name("a.b"), vec!["a", "b"]);
+        assert_eq!(split_name("`a.b`.`c``d`"), vec!["a.b", "c`d"]);
+    }
+
+    #[test]
+    fn qualified_name_should_parse() {
+        let name = QualifiedName::parse("orders").unwrap();
+        assert_eq!(name.schema, None);
+        assert_eq!(name.to_string(), "`orders`");
+
+        let name = QualifiedName::parse(" shop.orders ").unwrap();
+        assert_eq!(name.schema.as_deref(), Some("shop"));
+        assert_eq!(name.name, "orders");
+
+        let name = QualifiedName::parse("`my.db`.`odd``name`").unwrap();
+        assert_eq!(name.schema.as_deref(), Some("my.db"));
+        assert_eq!(name.name, "odd`name");
+        assert_eq!(name.to_string(), "`my.db`.`odd``name`");
+
+        assert!(QualifiedName::parse("a.b.c").is_err());
+        assert!(QualifiedName::parse("shop.").is_err());
+        assert!(QualifiedName::parse("").is_err());
+        assert!(QualifiedName::parse("`open").is_err());
+        assert!(QualifiedName::parse("`a`b").is_err());
+        assert!(QualifiedName::parse("t` ; DROP TABLE x; --").is_err());
+        assert!(QualifiedName::parse(&"x".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn ident_should_be_single_part() {
+        assert_eq!(parse_ident("idx_name").unwrap(), "idx_name");
+        assert_eq!(parse_ident("`a.b`").unwrap(), "a.b");
+        assert!(parse_ident("a.b").is_err());
+        assert_eq!(quote("we`ird"), "`we``ird`");
+    }
+}
@@ -1,5 +1,21 @@
+mod catalog;
+mod config;
+mod conn_spec;
+mod cursor;
+mod ddl;
+mod deadline;
+mod dry_run;
+mod guard;
+mod ident;
+mod mask;
 mod mcp;
 mod mysql;
+mod overview;
+mod policy;
+mod result_set;
 mod sqlx_mysql_tester;
+mod tls;
+mod transaction;
+mod value;
 pub use mysql::*;
 pub use sqlx_mysql_tester::*;
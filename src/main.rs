@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 use mysql_mcp::MySqlMcp;
 use rmcp::ServiceExt;
@@ -6,6 +8,12 @@ use rmcp::transport::sse_server::{SseServer, SseServerConfig};
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    /// Make every connection read-only and hide the tools that write
+    #[arg(long, global = true)]
+    read_only: bool,
+    /// TOML or YAML file declaring named connections
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,31 +41,33 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
+    let mut mcp = MySqlMcp::new().read_only(cli.read_only);
+    if let Some(path) = &cli.config {
+        mcp = mcp.config(path).await?;
+    }
+
     match cli.command {
-        Commands::Stdio => run_stdio_mode().await?,
-        Commands::Sse { port } => run_sse_mode(port).await?,
+        Commands::Stdio => run_stdio_mode(mcp).await?,
+        Commands::Sse { port } => run_sse_mode(port, mcp).await?,
     }
 
     Ok(())
 }
 
-async fn run_stdio_mode() -> anyhow::Result<()> {
+async fn run_stdio_mode(mcp: MySqlMcp) -> anyhow::Result<()> {
     tracing::info!("Starting MySQL MCP server in stdio mode");
 
-    // Create an instance of our MySQLMcp router
-    let service = MySqlMcp::new()
-        .serve(rmcp::transport::stdio())
-        .await
-        .inspect_err(|e| {
-            tracing::error!("serving error: {:?}", e);
-        })?;
+    // Serve our MySqlMcp router over stdio
+    let service = mcp.serve(rmcp::transport::stdio()).await.inspect_err(|e| {
+        tracing::error!("serving error: {:?}", e);
+    })?;
 
     service.waiting().await?;
 
     Ok(())
 }
 
-async fn run_sse_mode(port: u16) -> anyhow::Result<()> {
+async fn run_sse_mode(port: u16, mcp: MySqlMcp) -> anyhow::Result<()> {
     tracing::info!("Starting MySQL MCP server in SSE mode on port {}", port);
 
     let addr = format!("0.0.0.0:{}", port);
@@ -95,7 +105,7 @@ async fn run_sse_mode(port: u16) -> anyhow::Result<()> {
         }
     });
 
-    let service_ct = sse_server.with_service(MySqlMcp::new);
+    let service_ct = sse_server.with_service(move || mcp.session());
 
     tokio::signal::ctrl_c().await?;
     tracing::info!("Ctrl-C received, shutting down...");
@@ -0,0 +1,566 @@
+use std::ops::ControlFlow;
+
+use anyhow::Error;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlparser::ast::{Expr, ObjectName, Query, SelectItem, SetExpr, Statement, Visit, Visitor};
+
+use crate::ident::{QualifiedName, split_name};
+use crate::result_set::ColumnMeta;
+
+/// Replacement for redacted values.
+const REDACTED: &str = "[redacted]";
+/// Characters kept at the end of a partially masked value by default.
+const DEFAULT_KEEP_END: usize = 4;
+
+/// Masks the values of matching result columns before they are returned.
+///
+/// A rule matches on the base table a column was read from, so aliasing a
+/// column does not escape it. Columns whose origin can't be resolved, e.g.
+/// expressions, UNIONs or the columns of a view, are masked whenever the
+/// statement reads what a rule covers, as nothing proves they don't carry a
+/// masked value.
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MaskRule {
+    #[schemars(description = "Schema of the column, any schema when unset")]
+    pub schema: Option<String>,
+    #[schemars(description = "Table of the column, any table when unset")]
+    pub table: Option<String>,
+    #[schemars(description = "Column name")]
+    pub column: Option<String>,
+    #[schemars(
+        description = "Case insensitive regex matched against column names, e.g. email|phone"
+    )]
+    pub column_regex: Option<String>,
+    #[schemars(description = "How matching values are masked")]
+    pub action: MaskAction,
+    #[schemars(description = "For partial: leading characters left visible, defaults to 0")]
+    pub keep_start: Option<usize>,
+    #[schemars(description = "For partial: trailing characters left visible, defaults to 4")]
+    pub keep_end: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskAction {
+    /// Replace the value with `[redacted]`.
+    Redact,
+    /// Replace the value with its SHA-256, so equal values stay comparable.
+    Hash,
+    /// Replace all but the first and last few characters with `*`.
+    Partial,
+}
+
+/// A view a statement reads, with the tables behind it.
+#[derive(Debug, Clone)]
+pub(crate) struct View {
+    pub(crate) name: QualifiedName,
+    /// Every table it reads, through nested views too. `None` when the server
+    /// can't tell, which counts as reading every table.
+    pub(crate) tables: Option<Vec<QualifiedName>>,
+}
+
+/// Compiled mask rules of a connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Masks {
+    rules: Vec<(MaskRule, Option<Regex>)>,
+}
+
+impl Masks {
+    pub(crate) fn new(rules: &[MaskRule]) -> Result<Self, Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                if rule.column.is_none() && rule.column_regex.is_none() && rule.table.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Mask rule needs a table, column or column_regex"
+                    ));
+                }
+                let regex = rule
+                    .column_regex
+                    .as_deref()
+                    .map(|re| {
+                        RegexBuilder::new(re)
+                            .case_insensitive(true)
+                            .build()
+                            .map_err(|e| anyhow::anyhow!("Invalid column_regex {}: {}", re, e))
+                    })
+                    .transpose()?;
+                Ok((rule.clone(), regex))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { rules })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Decide how each of `columns`, the result of `statement`, is masked.
+    /// `views` are the views among the relations it reads.
+    pub(crate) fn plan(
+        &self,
+        statement: &Statement,
+        columns: &[ColumnMeta],
+        views: &[View],
+    ) -> MaskPlan {
+        if self.rules.is_empty() {
+            return MaskPlan::default();
+        }
+
+        let read = self.covered(&Reads::of(statement, views));
+        let actions = columns
+            .iter()
+            .map(|column| {
+                // a view hides the base column, only its name can be judged
+                let in_view = views.iter().any(|view| view.holds(column));
+                let unresolved = ColumnMeta {
+                    table: None,
+                    origin_column: None,
+                    ..column.clone()
+                };
+                let column = if in_view { &unresolved } else { column };
+                let unresolved = column.origin_column.is_none().then_some(read).flatten();
+                self.action(column).or(unresolved).cloned()
+            })
+            .collect();
+        MaskPlan { actions }
+    }
+
+    /// Fail when `statement` copies what a rule covers into a table, with
+    /// CREATE TABLE ... AS SELECT or INSERT ... SELECT, where it could be read
+    /// back unmasked.
+    pub(crate) fn check_copy(&self, statement: &Statement, views: &[View]) -> Result<(), Error> {
+        let source = match statement {
+            Statement::CreateTable(create) => create.query.as_deref(),
+            Statement::Insert(insert) => insert.source.as_deref(),
+            _ => None,
+        };
+        let Some(source) = source else {
+            return Ok(());
+        };
+        match self.covered(&Reads::of(source, views)) {
+            Some(_) => Err(anyhow::anyhow!(
+                "Copying masked columns into a table is not allowed"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The first rule covering what was read.
+    fn covered(&self, reads: &Reads) -> Option<&MaskRule> {
+        self.rules
+            .iter()
+            .find(|(rule, regex)| reads.covers(rule, regex.as_ref()))
+            .map(|(rule, _)| rule)
+    }
+
+    /// The first rule matching `column`.
+    fn action(&self, column: &ColumnMeta) -> Option<&MaskRule> {
+        self.rules
+            .iter()
+            .find(|(rule, regex)| matches(rule, regex.as_ref(), column))
+            .map(|(rule, _)| rule)
+    }
+}
+
+/// The mask rule of each result column, if any.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MaskPlan {
+    actions: Vec<Option<MaskRule>>,
+}
+
+impl MaskPlan {
+    /// Mask `rows` in place.
+    pub(crate) fn apply(&self, rows: &mut [Vec<Value>]) {
+        if self.actions.iter().all(Option::is_none) {
+            return;
+        }
+        for row in rows {
+            for (value, rule) in row.iter_mut().zip(&self.actions) {
+                if let Some(rule) = rule {
+                    mask(value, rule);
+                }
+            }
+        }
+    }
+}
+
+/// The relations `statement` reads or writes, CTE names included.
+pub(crate) fn relations(statement: &Statement) -> Vec<QualifiedName> {
+    let mut reads = Reads::default();
+    let _ = statement.visit(&mut reads);
+    reads.tables
+}
+
+impl View {
+    /// Whether `schema.name` may refer to this view, an unqualified one in
+    /// any schema.
+    fn is(&self, schema: Option<&str>, name: &str) -> bool {
+        name.eq_ignore_ascii_case(&self.name.name)
+            && schema
+                .zip(self.name.schema.as_deref())
+                .is_none_or(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// Whether `column` was read from this view.
+    fn holds(&self, column: &ColumnMeta) -> bool {
+        (column.table.as_deref()).is_some_and(|table| self.is(column.schema.as_deref(), table))
+    }
+}
+
+/// What a statement reads anywhere, subqueries included.
+#[derive(Debug, Default)]
+struct Reads {
+    tables: Vec<QualifiedName>,
+    /// Referenced column names, without their qualifier.
+    columns: Vec<String>,
+    /// Whether a projection has `*` or `t.*`, reading every column.
+    wildcard: bool,
+    /// Whether a view is read whose tables the server can't tell.
+    any_table: bool,
+}
+
+impl Visitor for Reads {
+    type Break = ();
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<Self::Break> {
+        let mut parts = split_name(&relation.to_string());
+        let name = parts.pop().unwrap_or_default();
+        self.tables.push(QualifiedName {
+            schema: parts.pop(),
+            name,
+        });
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        self.wildcard |= has_wildcard(&query.body);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        match expr {
+            Expr::Identifier(ident) => self.columns.push(ident.value.clone()),
+            Expr::CompoundIdentifier(idents) => {
+                self.columns.extend(idents.last().map(|i| i.value.clone()))
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl Reads {
+    /// What `node` reads, seeing through `views`. The columns of a view may be
+    /// renamed ones of its tables, so reading a view reads all of them.
+    fn of<V: Visit>(node: &V, views: &[View]) -> Self {
+        let mut reads = Reads::default();
+        let _ = node.visit(&mut reads);
+        let read: Vec<&View> = views
+            .iter()
+            .filter(|view| {
+                (reads.tables.iter()).any(|table| view.is(table.schema.as_deref(), &table.name))
+            })
+            .collect();
+        for view in read {
+            reads.wildcard = true;
+            match &view.tables {
+                Some(tables) => reads.tables.extend(tables.iter().cloned()),
+                None => reads.any_table = true,
+            }
+        }
+        reads
+    }
+
+    /// Whether a table and, for column rules, a column `rule` covers is read.
+    /// CTE names count as tables, which only errs on the side of masking.
+    fn covers(&self, rule: &MaskRule, regex: Option<&Regex>) -> bool {
+        let eq = |pattern: &Option<String>, name: &str| {
+            pattern
+                .as_ref()
+                .is_none_or(|p| p.eq_ignore_ascii_case(name))
+        };
+        let table = self.any_table
+            || self.tables.iter().any(|table| {
+                eq(&rule.table, &table.name)
+                    && table
+                        .schema
+                        .as_ref()
+                        .is_none_or(|schema| eq(&rule.schema, schema))
+            });
+        if !table || (rule.column.is_none() && regex.is_none()) {
+            return table;
+        }
+        self.wildcard
+            || self.columns.iter().any(|column| {
+                eq(&rule.column, column) && regex.is_none_or(|re| re.is_match(column))
+            })
+    }
+}
+
+fn has_wildcard(body: &SetExpr) -> bool {
+    match body {
+        SetExpr::Select(select) => select.projection.iter().any(|item| {
+            matches!(
+                item,
+                SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)
+            )
+        }),
+        SetExpr::SetOperation { left, right, .. } => has_wildcard(left) || has_wildcard(right),
+        _ => false,
+    }
+}
+
+fn matches(rule: &MaskRule, regex: Option<&Regex>, column: &ColumnMeta) -> bool {
+    let eq = |pattern: &Option<String>, name: Option<&str>| match (pattern, name) {
+        (None, _) => true,
+        (Some(pattern), Some(name)) => pattern.eq_ignore_ascii_case(name),
+        (Some(_), None) => false,
+    };
+
+    match &column.origin_column {
+        Some(origin) => {
+            // an unqualified origin lives in the connection's schema, which
+            // a schema rule can't be checked against, so it applies
+            let schema = column.schema.as_deref();
+            (rule.schema.is_none() || schema.is_none() || eq(&rule.schema, schema))
+                && eq(&rule.table, column.table.as_deref())
+                && eq(&rule.column, Some(origin.as_str()))
+                && regex.is_none_or(|re| re.is_match(origin) || re.is_match(&column.name))
+        }
+        // the base table is unknown, so only the name can be judged
+        None => {
+            (rule.column.is_some() || regex.is_some())
+                && eq(&rule.column, Some(column.name.as_str()))
+                && regex.is_none_or(|re| re.is_match(&column.name))
+        }
+    }
+}
+
+fn mask(value: &mut Value, rule: &MaskRule) {
+    let text = match &mut *value {
+        Value::Null => return,
+        Value::String(s) => std::mem::take(s),
+        other => other.to_string(),
+    };
+    *value = Value::String(match rule.action {
+        MaskAction::Redact => REDACTED.to_string(),
+        MaskAction::Hash => format!("{:x}", Sha256::digest(text.as_bytes())),
+        MaskAction::Partial => partial(
+            &text,
+            rule.keep_start.unwrap_or(0),
+            rule.keep_end.unwrap_or(DEFAULT_KEEP_END),
+        ),
+    });
+}
+
+/// `*` for every character except the kept ones. Values too short to hide
+/// anything are masked completely.
+fn partial(text: &str, keep_start: usize, keep_end: usize) -> String {
+    let len = text.chars().count();
+    if keep_start + keep_end >= len {
+        return "*".repeat(len);
+    }
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < keep_start || i >= len - keep_end {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn column(name: &str, table: Option<&str>, origin: Option<&str>) -> ColumnMeta {
+        ColumnMeta {
+            name: name.to_string(),
+            type_name: "VARCHAR".to_string(),
+            nullable: Some(true),
+            table: table.map(str::to_string),
+            schema: None,
+            origin_column: origin.map(str::to_string),
+        }
+    }
+
+    fn parse(sql: &str) -> Statement {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        sqlparser::parser::Parser::parse_sql(&dialect, sql)
+            .unwrap()
+            .remove(0)
+    }
+
+    fn masks(rules: serde_json::Value) -> Masks {
+        let rules: Vec<MaskRule> = serde_json::from_value(rules).unwrap();
+        Masks::new(&rules).unwrap()
+    }
+
+    #[test]
+    fn masks_should_follow_origin_columns() {
+        let masks = masks(json!([
+            { "table": "users", "column": "email", "action": "redact" },
+            { "column_regex": "^(phone|ssn)$", "action": "partial", "keep_end": 2 },
+        ]));
+        let columns = vec![
+            column("contact", Some("users"), Some("email")),
+            column("email", Some("orders"), Some("email")),
+            column("PHONE", None, None),
+            column("id", Some("users"), Some("id")),
+        ];
+        let mut rows = vec![vec![
+            json!("a@b.c"),
+            json!("x@y.z"),
+            json!(5551234),
+            json!(1),
+        ]];
+        let stmt = parse(
+            "SELECT u.email AS contact, o.email, phone, u.id FROM users u JOIN orders o ON o.user_id = u.id",
+        );
+        masks.plan(&stmt, &columns, &[]).apply(&mut rows);
+        assert_eq!(
+            rows[0],
+            vec![
+                json!("[redacted]"),
+                json!("x@y.z"),
+                json!("*****34"),
+                json!(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn masks_should_cover_unresolved_columns_reading_masked_tables() {
+        let masks = masks(json!([
+            { "table": "users", "column": "email", "action": "redact" },
+        ]));
+        let masked = |sql: &str| {
+            let columns = vec![
+                column("x", None, None),
+                column("id", Some("users"), Some("id")),
+            ];
+            let mut rows = vec![vec![json!("a@b.c"), json!(1)]];
+            masks.plan(&parse(sql), &columns, &[]).apply(&mut rows);
+            assert_eq!(rows[0][1], json!(1), "{}", sql);
+            rows[0][0] == json!("[redacted]")
+        };
+
+        assert!(masked("SELECT CONCAT(email, '') AS x, id FROM users"));
+        assert!(masked("SELECT LOWER(u.email) AS x, id FROM shop.users u"));
+        assert!(masked(
+            "SELECT d.x, d.id FROM (SELECT LOWER(email) AS x, id FROM users) d"
+        ));
+        assert!(masked("SELECT d.x, id FROM (SELECT * FROM users) d"));
+        assert!(masked(
+            "SELECT name AS x, id FROM orders UNION SELECT email, id FROM users"
+        ));
+        assert!(masked(
+            "WITH c AS (SELECT email FROM users) SELECT UPPER(email) AS x, 1 AS id FROM c"
+        ));
+
+        assert!(!masked("SELECT LOWER(name) AS x, id FROM users"));
+        assert!(!masked("SELECT CONCAT(email, '') AS x, id FROM orders"));
+        assert!(!masked("SELECT COUNT(*) AS x, 1 AS id FROM users"));
+    }
+
+    #[test]
+    fn masks_should_see_through_views() {
+        let masks = masks(json!([
+            { "table": "users", "column": "email", "action": "redact" },
+        ]));
+        let view = |name: &str, tables: Option<&[&str]>| View {
+            name: QualifiedName {
+                schema: None,
+                name: name.to_string(),
+            },
+            tables: tables.map(|tables| {
+                (tables.iter())
+                    .map(|table| QualifiedName {
+                        schema: Some("shop".to_string()),
+                        name: table.to_string(),
+                    })
+                    .collect()
+            }),
+        };
+        let masked = |sql: &str, views: &[View]| {
+            let columns = vec![
+                column("contact", Some("v"), Some("contact")),
+                column("id", Some("orders"), Some("id")),
+            ];
+            let mut rows = vec![vec![json!("a@b.c"), json!(1)]];
+            masks.plan(&parse(sql), &columns, views).apply(&mut rows);
+            assert_eq!(rows[0][1], json!(1), "{}", sql);
+            rows[0][0] == json!("[redacted]")
+        };
+
+        let sql = "SELECT v.contact, o.id FROM v JOIN orders o ON o.user_id = v.id";
+        assert!(masked(sql, &[view("v", Some(&["users"]))]));
+        assert!(masked(sql, &[view("v", None)]));
+        assert!(!masked(sql, &[view("v", Some(&["accounts"]))]));
+
+        let copy = |sql: &str, views: &[View]| masks.check_copy(&parse(sql), views).is_err();
+        assert!(copy(
+            "INSERT INTO t SELECT * FROM v",
+            &[view("v", Some(&["users"]))]
+        ));
+        assert!(!copy("INSERT INTO t SELECT * FROM v", &[]));
+    }
+
+    #[test]
+    fn masks_should_reject_copying_masked_columns() {
+        let masks = masks(json!([
+            { "table": "users", "column": "email", "action": "redact" },
+        ]));
+        let copy = |sql: &str| masks.check_copy(&parse(sql), &[]).is_err();
+
+        assert!(copy("CREATE TABLE t AS SELECT email FROM users"));
+        assert!(copy("CREATE TABLE t SELECT * FROM shop.users"));
+        assert!(copy("INSERT INTO t (e) SELECT LOWER(u.email) FROM users u"));
+        assert!(copy(
+            "INSERT INTO t VALUES ((SELECT email FROM users LIMIT 1))"
+        ));
+
+        assert!(!copy("CREATE TABLE t AS SELECT name FROM users"));
+        assert!(!copy("CREATE TABLE t (email VARCHAR(255))"));
+        assert!(!copy("INSERT INTO users (email) VALUES ('a@b.c')"));
+        assert!(!copy("INSERT INTO t SELECT email FROM orders"));
+        let create = parse("CREATE TABLE t AS SELECT email FROM users");
+        assert!(Masks::default().check_copy(&create, &[]).is_ok());
+    }
+
+    #[test]
+    fn masks_should_hash_and_keep_nulls() {
+        let masks = masks(json!([{ "column": "token", "action": "hash" }]));
+        let columns = vec![column("t", Some("sessions"), Some("token"))];
+        let mut rows = vec![vec![json!("abc")], vec![json!(null)]];
+        let stmt = parse("SELECT token AS t FROM sessions");
+        masks.plan(&stmt, &columns, &[]).apply(&mut rows);
+        assert_eq!(
+            rows[0][0],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(rows[1][0], json!(null));
+
+        assert_eq!(partial("secret", 1, 1), "s****t");
+        assert_eq!(partial("abc", 0, 4), "***");
+        assert!(
+            Masks::new(
+                &serde_json::from_value::<Vec<MaskRule>>(json!([
+                    { "column_regex": "(", "action": "hash" }
+                ]))
+                .unwrap()
+            )
+            .is_err()
+        );
+    }
+}
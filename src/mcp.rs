@@ -1,14 +1,97 @@
 use crate::MySqlMcp;
+use crate::conn_spec::{ConnFields, ConnSpec};
+use crate::ddl::ObjectKind;
+use crate::mask::MaskRule;
+use crate::mysql::{ConnOptions, ExecOptions, QueryOptions};
+use crate::overview::DEFAULT_MAX_TOKENS;
+use crate::policy::PolicyViolation;
+use crate::tls::TlsOptions;
 use rmcp::{
-    Error as McpError, ServerHandler,
-    model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
-    schemars, tool,
+    Error as McpError, RoleServer, ServerHandler,
+    handler::server::tool::ToolCallContext,
+    model::{
+        CallToolRequestParam, CallToolResult, Content, ListToolsResult, PaginatedRequestParam,
+        ServerCapabilities, ServerInfo,
+    },
+    schemars,
+    service::RequestContext,
+    tool,
 };
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct RegisterRequest {
-    #[schemars(description = "Mysql connection string")]
-    pub conn_str: String,
+    #[schemars(
+        description = "Mysql connection string. Give either this or connection",
+        with = "Option<String>"
+    )]
+    pub conn_str: Option<ConnSpec>,
+    #[schemars(
+        description = "The connection field by field instead of conn_str: host, port, socket, user, password, database, charset and options"
+    )]
+    pub connection: Option<ConnFields>,
+    #[schemars(description = "Maximum number of rows a query returns, defaults to 1000")]
+    pub max_rows: Option<usize>,
+    #[schemars(
+        description = "Maximum size in bytes of the JSON encoded rows a query returns, defaults to 1MiB"
+    )]
+    pub max_bytes: Option<usize>,
+    #[schemars(
+        description = "Default statement timeout in milliseconds, defaults to 30000. Statements running longer are killed"
+    )]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Milliseconds an open transaction may sit unused before it is rolled back, defaults to 60000"
+    )]
+    pub tx_idle_timeout_ms: Option<u64>,
+    #[schemars(
+        description = "UPDATE and DELETE statements changing more rows than this are rolled back with an error"
+    )]
+    pub max_rows_affected: Option<u64>,
+    #[schemars(
+        description = "Path of a TOML or YAML access policy file checked before every statement"
+    )]
+    pub policy_file: Option<String>,
+    #[schemars(
+        description = "Only allow SELECT, SHOW, EXPLAIN and DESCRIBE, with every session set to READ ONLY"
+    )]
+    pub read_only: Option<bool>,
+    #[schemars(
+        description = "Rules masking column values in query results, matched by origin schema/table/column or by column_regex"
+    )]
+    pub masks: Option<Vec<MaskRule>>,
+    #[schemars(description = "Most pooled connections, defaults to 10")]
+    pub max_connections: Option<u32>,
+    #[schemars(description = "Pooled connections kept open even when idle, defaults to 0")]
+    pub min_connections: Option<u32>,
+    #[schemars(
+        description = "Milliseconds to wait for a free pooled connection, defaults to 30000"
+    )]
+    pub acquire_timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Milliseconds after which an idle pooled connection is closed, defaults to 600000"
+    )]
+    pub idle_timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Milliseconds after which a pooled connection is replaced, defaults to 1800000"
+    )]
+    pub max_lifetime_ms: Option<u64>,
+    #[schemars(description = "Session time_zone, e.g. +00:00 or Europe/Berlin")]
+    pub time_zone: Option<String>,
+    #[schemars(description = "Session sql_mode")]
+    pub sql_mode: Option<String>,
+    #[schemars(description = "Connection character set, e.g. utf8mb4")]
+    pub charset: Option<String>,
+    #[schemars(
+        description = "TLS settings: ssl_mode (disabled, preferred, required, verify_ca, verify_identity), ca, client_cert and client_key file paths"
+    )]
+    pub tls: Option<TlsOptions>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ConnectionStatusRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -22,9 +105,67 @@ pub struct QueryRequest {
     #[schemars(description = "Connection ID")]
     pub conn_id: String,
     #[schemars(
-        description = "Single SQL query, could return multiple rows. Caller should properly limit the number of rows returned."
+        description = "Single SQL query, could return multiple rows. Results are capped by the connection's row and byte limits, check `truncated` in the response."
     )]
     pub query: String,
+    #[schemars(
+        description = "Maximum number of rows to return, can only lower the connection's limit"
+    )]
+    pub max_rows: Option<usize>,
+    #[schemars(
+        description = "Read the result in pages of this many rows. While rows remain the response has a `cursor` to pass to query_next"
+    )]
+    pub page_size: Option<usize>,
+    #[schemars(
+        description = "Values bound in order to the `?` placeholders of the statement. Use placeholders instead of inlining values into the SQL"
+    )]
+    pub params: Option<Vec<serde_json::Value>>,
+    #[schemars(description = "Transaction ID from begin_transaction to run the statement in")]
+    pub tx_id: Option<String>,
+    #[schemars(description = "Statement timeout in milliseconds, overrides the connection's")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct QueryNextRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(description = "Cursor returned by a previous query or query_next call")]
+    pub cursor: String,
+    #[schemars(description = "Statement timeout in milliseconds, overrides the connection's")]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BeginTransactionRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CommitRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(description = "Transaction ID returned by begin_transaction")]
+    pub tx_id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RollbackRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(description = "Transaction ID returned by begin_transaction")]
+    pub tx_id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SavepointRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(description = "Transaction ID returned by begin_transaction")]
+    pub tx_id: String,
+    #[schemars(description = "Savepoint name")]
+    pub name: String,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -35,6 +176,14 @@ pub struct InsertRequest {
         description = "Single SQL insert statement, but multiple rows for the same table are allowed"
     )]
     pub query: String,
+    #[schemars(
+        description = "Values bound in order to the `?` placeholders of the statement. Use placeholders instead of inlining values into the SQL"
+    )]
+    pub params: Option<Vec<serde_json::Value>>,
+    #[schemars(description = "Transaction ID from begin_transaction to run the statement in")]
+    pub tx_id: Option<String>,
+    #[schemars(description = "Statement timeout in milliseconds, overrides the connection's")]
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -45,6 +194,22 @@ pub struct UpdateRequest {
         description = "Single SQL update statement, could update multiple rows for the same table based on the WHERE clause"
     )]
     pub query: String,
+    #[schemars(
+        description = "Values bound in order to the `?` placeholders of the statement. Use placeholders instead of inlining values into the SQL"
+    )]
+    pub params: Option<Vec<serde_json::Value>>,
+    #[schemars(description = "Transaction ID from begin_transaction to run the statement in")]
+    pub tx_id: Option<String>,
+    #[schemars(description = "Statement timeout in milliseconds, overrides the connection's")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Execute and roll back, returning rows_affected and a sample of the matched rows instead of changing anything"
+    )]
+    pub dry_run: Option<bool>,
+    #[schemars(
+        description = "Run even without a WHERE clause, or with one that matches every row such as 1=1"
+    )]
+    pub allow_full_table: Option<bool>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -55,6 +220,22 @@ pub struct DeleteRequest {
         description = "Single SQL delete statement, could delete multiple rows for the same table based on the WHERE clause"
     )]
     pub query: String,
+    #[schemars(
+        description = "Values bound in order to the `?` placeholders of the statement. Use placeholders instead of inlining values into the SQL"
+    )]
+    pub params: Option<Vec<serde_json::Value>>,
+    #[schemars(description = "Transaction ID from begin_transaction to run the statement in")]
+    pub tx_id: Option<String>,
+    #[schemars(description = "Statement timeout in milliseconds, overrides the connection's")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Execute and roll back, returning rows_affected and a sample of the matched rows instead of changing anything"
+    )]
+    pub dry_run: Option<bool>,
+    #[schemars(
+        description = "Run even without a WHERE clause, or with one that matches every row such as 1=1"
+    )]
+    pub allow_full_table: Option<bool>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -89,7 +270,9 @@ pub struct DropIndexRequest {
     pub conn_id: String,
     #[schemars(description = "Index name")]
     pub index: String,
-    #[schemars(description = "Table name")]
+    #[schemars(
+        description = "Table name. Format: schema.table. If schema is not provided, it will use the current schema."
+    )]
     pub table: String,
 }
 
@@ -97,16 +280,60 @@ pub struct DropIndexRequest {
 pub struct DescribeRequest {
     #[schemars(description = "Connection ID")]
     pub conn_id: String,
-    #[schemars(description = "Table name")]
+    #[schemars(
+        description = "Table name. Format: schema.table. If schema is not provided, it will use the current schema."
+    )]
     pub table: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetDdlRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(
+        description = "Kind of the object: table, view, procedure, function, trigger or event. Defaults to table"
+    )]
+    pub kind: Option<ObjectKind>,
+    #[schemars(
+        description = "Object name, optionally qualified as schema.name. Leave it out for the DDL of the whole schema"
+    )]
+    pub name: Option<String>,
+    #[schemars(description = "Schema name, defaults to the connection's current schema")]
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SchemaOverviewRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(description = "Schema name, defaults to the connection's current schema")]
+    pub schema: Option<String>,
+    #[schemars(
+        description = "Approximate size limit of the overview in tokens, defaults to 4000. Tables that don't fit are only named"
+    )]
+    pub max_tokens: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListSchemasRequest {
+    #[schemars(description = "Connection ID")]
+    pub conn_id: String,
+    #[schemars(
+        description = "Also list information_schema, mysql, performance_schema and sys, defaults to false"
+    )]
+    pub include_system: Option<bool>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ListTablesRequest {
     #[schemars(description = "Connection ID")]
     pub conn_id: String,
-    #[schemars(description = "Schema name")]
-    pub schema: String,
+    #[schemars(description = "Schema name, defaults to the connection's current schema")]
+    pub schema: Option<String>,
+    #[schemars(
+        description = "Kind of objects to list: table (the default), view, procedure, function, trigger or event. Views, routines, triggers and events come with their definitions, routines also with their parameter signatures"
+    )]
+    pub kind: Option<ObjectKind>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -122,72 +349,316 @@ impl MySqlMcp {
     pub fn new() -> Self {
         Self {
             conns: crate::mysql::Conns::new(),
+            read_only: false,
         }
     }
 
-    #[tool(description = "Register a new Postgres connection")]
+    /// Make every connection read-only and hide the tools that write.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Connect the named connections declared in the config file at `path`,
+    /// read-only if the server already is.
+    pub async fn config(self, path: &std::path::Path) -> anyhow::Result<Self> {
+        self.conns.load_config(path, self.read_only).await?;
+        Ok(self)
+    }
+
+    /// A server for another client session. It shares the configured
+    /// connections, but not the ones registered by other sessions.
+    pub fn session(&self) -> Self {
+        Self {
+            conns: self.conns.fork(),
+            read_only: self.read_only,
+        }
+    }
+
+    #[tool(
+        description = "Register a new MySQL connection, given as conn_str or field by field as connection. Returns its conn_id"
+    )]
     async fn register(
         &self,
         #[tool(aggr)] req: RegisterRequest,
     ) -> Result<CallToolResult, McpError> {
+        let spec =
+            ConnSpec::either(req.conn_str, req.connection, "conn_str").map_err(tool_error)?;
+        if spec.reads_password_reference() {
+            return Err(McpError::invalid_params(
+                "password_file and password_env are only accepted in the config file",
+                None,
+            ));
+        }
         let id = self
             .conns
-            .register(req.conn_str)
+            .register(
+                spec,
+                ConnOptions {
+                    read_only: self.read_only || req.read_only.unwrap_or(false),
+                    max_rows: req.max_rows,
+                    max_bytes: req.max_bytes,
+                    timeout_ms: req.timeout_ms,
+                    tx_idle_timeout_ms: req.tx_idle_timeout_ms,
+                    max_rows_affected: req.max_rows_affected,
+                    policy_file: req.policy_file.map(Into::into),
+                    masks: req.masks.unwrap_or_default(),
+                    max_connections: req.max_connections,
+                    min_connections: req.min_connections,
+                    acquire_timeout_ms: req.acquire_timeout_ms,
+                    idle_timeout_ms: req.idle_timeout_ms,
+                    max_lifetime_ms: req.max_lifetime_ms,
+                    time_zone: req.time_zone,
+                    sql_mode: req.sql_mode,
+                    charset: req.charset,
+                    tls: req.tls.unwrap_or_default(),
+                },
+            )
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(id)]))
     }
 
-    #[tool(description = "Unregister a Postgres connection")]
+    #[tool(
+        description = "List the available connections: conn_id, host, port, user, database, read_only, whether a policy applies and whether it comes from the config file"
+    )]
+    async fn list_connections(&self) -> Result<CallToolResult, McpError> {
+        let result = self.conns.list_connections().map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Report pool size, idle connections, pool limits, server version, the negotiated TLS version and cipher, and the latency of a SELECT 1 ping"
+    )]
+    async fn connection_status(
+        &self,
+        #[tool(aggr)] req: ConnectionStatusRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .connection_status(&req.conn_id)
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Unregister a MySQL connection, closing its open cursors and rolling back its transactions. Connections from the config file can't be unregistered"
+    )]
     async fn unregister(
         &self,
         #[tool(aggr)] req: UnregisterRequest,
+    ) -> Result<CallToolResult, McpError> {
+        self.conns.unregister(req.conn_id).map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(
+            "success".to_string(),
+        )]))
+    }
+
+    #[tool(
+        description = "Execute a SELECT query, or SHOW, EXPLAIN and DESCRIBE on a read-only connection. Returns {columns, rows, row_count, rows_seen, truncated, elapsed_ms}, where each row is an array of values in column order"
+    )]
+    async fn query(
+        &self,
+        #[tool(aggr)] req: QueryRequest,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .query(
+                &req.conn_id,
+                &req.query,
+                QueryOptions {
+                    max_rows: req.max_rows,
+                    page_size: req.page_size,
+                    params: req.params.unwrap_or_default(),
+                    tx_id: req.tx_id,
+                    timeout_ms: req.timeout_ms,
+                    ct,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Fetch the next page of a paginated query. Cursors expire after 5 minutes without use"
+    )]
+    async fn query_next(
+        &self,
+        #[tool(aggr)] req: QueryNextRequest,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .query_next(&req.conn_id, &req.cursor, req.timeout_ms, ct)
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Start a transaction and return its ID. Pass it as tx_id to query, insert, update and delete, then commit or rollback. Idle transactions are rolled back automatically"
+    )]
+    async fn begin_transaction(
+        &self,
+        #[tool(aggr)] req: BeginTransactionRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let tx_id = self
+            .conns
+            .begin_transaction(&req.conn_id)
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(tx_id)]))
+    }
+
+    #[tool(description = "Commit a transaction")]
+    async fn commit(&self, #[tool(aggr)] req: CommitRequest) -> Result<CallToolResult, McpError> {
+        self.conns
+            .commit(&req.conn_id, &req.tx_id)
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(
+            "success".to_string(),
+        )]))
+    }
+
+    #[tool(description = "Roll back a transaction")]
+    async fn rollback(
+        &self,
+        #[tool(aggr)] req: RollbackRequest,
     ) -> Result<CallToolResult, McpError> {
         self.conns
-            .unregister(req.conn_id)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .rollback(&req.conn_id, &req.tx_id)
+            .await
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(
             "success".to_string(),
         )]))
     }
 
-    #[tool(description = "Execute a SELECT query")]
-    async fn query(&self, #[tool(aggr)] req: QueryRequest) -> Result<CallToolResult, McpError> {
+    #[tool(
+        description = "Set a named savepoint in a transaction. Returns the savepoint stack, oldest first"
+    )]
+    async fn savepoint(
+        &self,
+        #[tool(aggr)] req: SavepointRequest,
+    ) -> Result<CallToolResult, McpError> {
         let result = self
             .conns
-            .query(&req.conn_id, &req.query)
+            .savepoint(&req.conn_id, &req.tx_id, &req.name)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Undo the changes made since a savepoint, keeping the savepoint and dropping later ones. Returns the savepoint stack"
+    )]
+    async fn rollback_to_savepoint(
+        &self,
+        #[tool(aggr)] req: SavepointRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .rollback_to_savepoint(&req.conn_id, &req.tx_id, &req.name)
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Remove a savepoint and every later one, keeping their changes. Returns the savepoint stack"
+    )]
+    async fn release_savepoint(
+        &self,
+        #[tool(aggr)] req: SavepointRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .release_savepoint(&req.conn_id, &req.tx_id, &req.name)
+            .await
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
     #[tool(description = "Execute an INSERT statement")]
-    async fn insert(&self, #[tool(aggr)] req: InsertRequest) -> Result<CallToolResult, McpError> {
+    async fn insert(
+        &self,
+        #[tool(aggr)] req: InsertRequest,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
         let result = self
             .conns
-            .insert(&req.conn_id, &req.query)
+            .insert(
+                &req.conn_id,
+                &req.query,
+                ExecOptions {
+                    params: req.params.unwrap_or_default(),
+                    tx_id: req.tx_id,
+                    timeout_ms: req.timeout_ms,
+                    ct,
+                    ..Default::default()
+                },
+            )
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
-    #[tool(description = "Execute an UPDATE statement")]
-    async fn update(&self, #[tool(aggr)] req: UpdateRequest) -> Result<CallToolResult, McpError> {
+    #[tool(
+        description = "Execute an UPDATE statement. Refused without a restricting WHERE clause unless allow_full_table is set. dry_run executes it and rolls back, returning rows_affected and a sample of the matched rows. Changing more rows than the connection's max_rows_affected rolls back with an error"
+    )]
+    async fn update(
+        &self,
+        #[tool(aggr)] req: UpdateRequest,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
         let result = self
             .conns
-            .update(&req.conn_id, &req.query)
+            .update(
+                &req.conn_id,
+                &req.query,
+                ExecOptions {
+                    params: req.params.unwrap_or_default(),
+                    tx_id: req.tx_id,
+                    dry_run: req.dry_run.unwrap_or_default(),
+                    allow_full_table: req.allow_full_table.unwrap_or_default(),
+                    timeout_ms: req.timeout_ms,
+                    ct,
+                },
+            )
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
-    #[tool(description = "Delete a row from a table")]
-    async fn delete(&self, #[tool(aggr)] req: DeleteRequest) -> Result<CallToolResult, McpError> {
+    #[tool(
+        description = "Execute a DELETE statement. Refused without a restricting WHERE clause unless allow_full_table is set. dry_run executes it and rolls back, returning rows_affected and a sample of the matched rows. Deleting more rows than the connection's max_rows_affected rolls back with an error"
+    )]
+    async fn delete(
+        &self,
+        #[tool(aggr)] req: DeleteRequest,
+        ct: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
         let result = self
             .conns
-            .delete(&req.conn_id, &req.query)
+            .delete(
+                &req.conn_id,
+                &req.query,
+                ExecOptions {
+                    params: req.params.unwrap_or_default(),
+                    tx_id: req.tx_id,
+                    dry_run: req.dry_run.unwrap_or_default(),
+                    allow_full_table: req.allow_full_table.unwrap_or_default(),
+                    timeout_ms: req.timeout_ms,
+                    ct,
+                },
+            )
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
@@ -200,7 +671,7 @@ impl MySqlMcp {
             .conns
             .create_table(&req.conn_id, &req.query)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
@@ -213,7 +684,7 @@ impl MySqlMcp {
             .conns
             .drop_table(&req.conn_id, &req.table)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
@@ -226,7 +697,7 @@ impl MySqlMcp {
             .conns
             .create_index(&req.conn_id, &req.query)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
@@ -239,11 +710,13 @@ impl MySqlMcp {
             .conns
             .drop_index(&req.conn_id, &req.index, &req.table)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
-    #[tool(description = "Describe a table")]
+    #[tool(
+        description = "Describe a table: engine, row estimate and comment, columns with their full type, default, nullability, extra, collation and comment, the primary key, indexes, foreign keys in both directions and CHECK constraints"
+    )]
     async fn describe(
         &self,
         #[tool(aggr)] req: DescribeRequest,
@@ -252,20 +725,77 @@ impl MySqlMcp {
             .conns
             .describe(&req.conn_id, &req.table)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Get the exact SHOW CREATE output of a table, view, procedure, function, trigger or event, or of every object in a schema in an order they can be created in"
+    )]
+    async fn get_ddl(&self, #[tool(aggr)] req: GetDdlRequest) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .get_ddl(
+                &req.conn_id,
+                req.kind.unwrap_or(ObjectKind::Table),
+                req.name.as_deref(),
+                req.schema.as_deref(),
+            )
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "Get a compact text overview of a schema in one call: every table with its columns, types, primary and foreign keys and row estimate, the most connected tables first, trimmed to a token budget"
+    )]
+    async fn schema_overview(
+        &self,
+        #[tool(aggr)] req: SchemaOverviewRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .schema_overview(
+                &req.conn_id,
+                req.schema.as_deref(),
+                req.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            )
+            .await
+            .map_err(tool_error)?;
+        Ok(CallToolResult::success(vec![Content::text(result)]))
+    }
+
+    #[tool(
+        description = "List the schemas with their charset, collation, table and view counts and size in bytes, and which one is current"
+    )]
+    async fn list_schemas(
+        &self,
+        #[tool(aggr)] req: ListSchemasRequest,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .conns
+            .list_schemas(&req.conn_id, req.include_system.unwrap_or(false))
+            .await
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
-    #[tool(description = "List all tables")]
+    #[tool(
+        description = "List the tables of a schema, or its views, procedures, functions, triggers or events"
+    )]
     async fn list_tables(
         &self,
         #[tool(aggr)] req: ListTablesRequest,
     ) -> Result<CallToolResult, McpError> {
         let result = self
             .conns
-            .list_tables(&req.conn_id, &req.schema)
+            .list_tables(
+                &req.conn_id,
+                req.schema.as_deref(),
+                req.kind.unwrap_or(ObjectKind::Table),
+            )
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 
@@ -278,23 +808,68 @@ impl MySqlMcp {
             .conns
             .create_schema(&req.conn_id, &req.name)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            .map_err(tool_error)?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 }
 
-#[tool(tool_box)]
+/// Tools hidden from a read-only server.
+const WRITE_TOOLS: &[&str] = &[
+    "insert",
+    "update",
+    "delete",
+    "create_table",
+    "drop_table",
+    "create_index",
+    "drop_index",
+    "create_schema",
+];
+
 impl ServerHandler for MySqlMcp {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "A Postgres MCP server that allows AI agents to interact with Postgres databases"
-                    .into(),
+                "A MySQL MCP server that allows AI agents to interact with MySQL databases".into(),
             ),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             ..Default::default()
         }
     }
+
+    async fn list_tools(
+        &self,
+        _: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let tools = Self::tool_box()
+            .list()
+            .into_iter()
+            .filter(|tool| !(self.read_only && WRITE_TOOLS.contains(&tool.name.as_ref())))
+            .collect();
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let context = ToolCallContext::new(self, request, context);
+        Self::tool_box().call(context).await
+    }
+}
+
+/// Policy violations are the caller's fault and carry the rule that fired.
+fn tool_error(e: anyhow::Error) -> McpError {
+    match e.downcast_ref::<PolicyViolation>() {
+        Some(violation) => {
+            McpError::invalid_request(e.to_string(), serde_json::to_value(violation).ok())
+        }
+        None => McpError::internal_error(e.to_string(), None),
+    }
 }
 
 impl Default for MySqlMcp {
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Error;
+use sqlx::mysql::MySqlPool;
+
+use crate::ident::QualifiedName;
+use crate::policy::Policy;
+
+/// Token budget of an overview when the call doesn't set one.
+pub(crate) const DEFAULT_MAX_TOKENS: usize = 4000;
+/// Rough number of characters per token, used to keep within a budget.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// A table as listed in an overview.
+#[derive(Debug, Default)]
+struct Table {
+    name: String,
+    rows: Option<i64>,
+    comment: Option<String>,
+    /// `name type`, plus `PK`, `NULL` and `-> table.column` markers.
+    columns: Vec<String>,
+    /// Tables with a foreign key to this one.
+    referenced_by: Vec<String>,
+    /// Foreign key columns in and out, which decides the order.
+    links: usize,
+}
+
+/// A compact text digest of the tables in `schema` that `policy` allows:
+/// columns with their types, keys and row estimates. The most connected
+/// tables come first, and tables that don't fit in `max_tokens` are only
+/// named, as far as the budget allows.
+pub(crate) async fn schema_overview(
+    pool: &MySqlPool,
+    schema: &str,
+    policy: Option<&Policy>,
+    max_tokens: usize,
+) -> Result<String, Error> {
+    let query = r#"
+      SELECT TABLE_NAME, CAST(TABLE_ROWS AS SIGNED), NULLIF(TABLE_COMMENT, '')
+      FROM information_schema.tables
+      WHERE TABLE_SCHEMA = ? AND TABLE_TYPE = 'BASE TABLE'
+      ORDER BY TABLE_NAME
+    "#;
+    let mut tables: BTreeMap<String, Table> = BTreeMap::new();
+    for (name, rows, comment) in sqlx::query_as::<_, (String, Option<i64>, Option<String>)>(query)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?
+    {
+        let allowed = policy.is_none_or(|policy| {
+            policy
+                .check_table(&QualifiedName {
+                    schema: Some(schema.to_string()),
+                    name: name.clone(),
+                })
+                .is_ok()
+        });
+        if allowed {
+            tables.insert(
+                name.clone(),
+                Table {
+                    name,
+                    rows,
+                    comment,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let query = r#"
+      SELECT
+        TABLE_NAME, COLUMN_NAME, REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME,
+        REFERENCED_COLUMN_NAME
+      FROM information_schema.key_column_usage
+      WHERE TABLE_SCHEMA = ? AND REFERENCED_TABLE_NAME IS NOT NULL
+      ORDER BY TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION
+    "#;
+    let mut references: BTreeMap<(String, String), String> = BTreeMap::new();
+    for (table, column, referenced_schema, referenced_table, referenced_column) in
+        sqlx::query_as::<_, (String, String, String, String, String)>(query)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?
+    {
+        if !tables.contains_key(&table) {
+            continue;
+        }
+        let target = if referenced_schema == schema {
+            if let Some(referenced) = tables.get_mut(&referenced_table) {
+                referenced.links += 1;
+                if referenced_table != table && !referenced.referenced_by.contains(&table) {
+                    referenced.referenced_by.push(table.clone());
+                }
+            }
+            format!("{}.{}", referenced_table, referenced_column)
+        } else {
+            format!(
+                "{}.{}.{}",
+                referenced_schema, referenced_table, referenced_column
+            )
+        };
+        if let Some(table) = tables.get_mut(&table) {
+            table.links += 1;
+        }
+        references.insert((table, column), target);
+    }
+
+    let query = r#"
+      SELECT TABLE_NAME, COLUMN_NAME, CAST(COLUMN_TYPE AS CHAR), IS_NULLABLE, COLUMN_KEY
+      FROM information_schema.columns
+      WHERE TABLE_SCHEMA = ?
+      ORDER BY TABLE_NAME, ORDINAL_POSITION
+    "#;
+    for (table, column, column_type, nullable, key) in
+        sqlx::query_as::<_, (String, String, String, String, String)>(query)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?
+    {
+        let Some(entry) = tables.get_mut(&table) else {
+            continue;
+        };
+        let mut line = format!("{} {}", column, column_type);
+        if key == "PRI" {
+            line.push_str(" PK");
+        }
+        if nullable == "YES" {
+            line.push_str(" NULL");
+        }
+        if let Some(target) = references.get(&(table, column)) {
+            write!(line, " -> {}", target)?;
+        }
+        entry.columns.push(line);
+    }
+
+    let mut tables: Vec<Table> = tables.into_values().collect();
+    // stable, so equally connected tables stay in name order
+    tables.sort_by_key(|table| std::cmp::Reverse(table.links));
+    Ok(render(schema, &tables, max_tokens))
+}
+
+/// Render `tables` in order until the budget runs out, then name as many of
+/// the rest as still fit. Only the header and the count of missing tables
+/// may go over a budget too small for them.
+fn render(schema: &str, tables: &[Table], max_tokens: usize) -> String {
+    let budget = max_tokens.saturating_mul(CHARS_PER_TOKEN);
+    let mut out = format!(
+        "Schema {}: {} tables, most connected first. PK = primary key, NULL = nullable, -> = foreign key.\n",
+        schema,
+        tables.len()
+    );
+
+    let footer = |rest: usize| format!("\n{} more tables not shown, use describe for them:", rest);
+    let mut shown = 0;
+    for (i, table) in tables.iter().enumerate() {
+        let mut block = format!("\n{}", table.name);
+        if let Some(rows) = table.rows {
+            write!(block, " ~{} rows", rows).unwrap();
+        }
+        if !table.referenced_by.is_empty() {
+            write!(block, ", referenced by {}", table.referenced_by.join(", ")).unwrap();
+        }
+        if let Some(comment) = &table.comment {
+            write!(block, " -- {}", comment).unwrap();
+        }
+        write!(block, "\n  {}\n", table.columns.join(", ")).unwrap();
+        // leave room to say that tables are missing
+        let rest = tables.len() - i - 1;
+        let reserve = if rest > 0 { footer(rest).len() + 1 } else { 0 };
+        if out.len() + block.len() + reserve > budget {
+            break;
+        }
+        out.push_str(&block);
+        shown += 1;
+    }
+
+    let rest = &tables[shown..];
+    if !rest.is_empty() {
+        out.push_str(&footer(rest.len()));
+        for (i, table) in rest.iter().enumerate() {
+            let name = format!(" {}", table.name);
+            let ellipsis = if i + 1 < rest.len() { " ...".len() } else { 0 };
+            if out.len() + name.len() + ellipsis + 1 > budget {
+                out.push_str(" ...");
+                break;
+            }
+            out.push_str(&name);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(name: &str, links: usize, columns: &[&str]) -> Table {
+        Table {
+            name: name.to_string(),
+            rows: Some(10),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            links,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn overview_should_fit_the_budget() {
+        let tables = vec![
+            Table {
+                referenced_by: vec!["items".to_string()],
+                comment: Some("customer orders".to_string()),
+                ..table("orders", 2, &["id int PK", "total decimal(10,2) NULL"])
+            },
+            table("items", 1, &["id int PK", "order_id int -> orders.id"]),
+            table("audit_log", 0, &["id bigint PK", "message text"]),
+        ];
+
+        let overview = render("shop", &tables, 1000);
+        assert!(overview.contains(
+            "orders ~10 rows, referenced by items -- customer orders\n  id int PK, total decimal(10,2) NULL\n"
+        ));
+        assert!(overview.find("orders ~10").unwrap() < overview.find("items ~10").unwrap());
+        assert!(overview.contains("audit_log ~10 rows"));
+        assert!(!overview.contains("not shown"));
+
+        let overview = render("shop", &tables, 65);
+        assert!(overview.len() <= 65 * CHARS_PER_TOKEN);
+        assert!(overview.contains("orders ~10 rows"));
+        assert!(overview.contains("2 more tables not shown, use describe for them: items"));
+        assert!(!overview.contains("items ~10 rows"));
+    }
+}
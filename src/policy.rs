@@ -0,0 +1,878 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    Expr, ObjectName, ObjectType, Query, SelectItem, SetExpr, ShowCreateObject,
+    ShowStatementOptions, Statement, TableFactor, Visit, Visitor,
+};
+
+use crate::ident::{QualifiedName, split_name};
+
+/// Statement kinds a policy can name in `allowed_statements`.
+const STATEMENT_KINDS: &[&str] = &[
+    "select",
+    "insert",
+    "update",
+    "delete",
+    "create_table",
+    "alter_table",
+    "drop_table",
+    "truncate",
+    "create_index",
+    "drop_index",
+    "create_view",
+    "drop_view",
+    "create_schema",
+    "drop_schema",
+    "show",
+    "explain",
+    "describe",
+    "other",
+];
+
+/// Functions rejected when a policy does not list its own.
+const DEFAULT_FORBIDDEN_FUNCTIONS: &[&str] = &["SLEEP", "LOAD_FILE", "BENCHMARK", "GET_LOCK"];
+
+/// Access rules attached to a connection, loaded from a TOML or YAML file.
+///
+/// Every name pattern may use `*` as a wildcard and is compared case
+/// insensitively. Table patterns are `table` or `schema.table`, column
+/// patterns are `column`, `table.column` or `schema.table.column`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Policy {
+    /// Statement kinds that may run, everything when empty.
+    #[serde(default)]
+    allowed_statements: Vec<String>,
+    #[serde(default)]
+    schemas: Rules,
+    #[serde(default)]
+    tables: Rules,
+    #[serde(default)]
+    columns: Rules,
+    #[serde(default = "default_forbidden_functions")]
+    forbidden_functions: Vec<String>,
+    /// Schema of unqualified names, the connection's `DATABASE()`.
+    #[serde(skip)]
+    pub(crate) default_schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rules {
+    /// When not empty, only matching names are allowed.
+    #[serde(default)]
+    allow: Vec<String>,
+    /// Matching names are rejected, even if they are also allowed.
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+/// A statement rejected by a [`Policy`], naming the rule that fired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct PolicyViolation {
+    /// e.g. `tables.deny` or `forbidden_functions`.
+    pub(crate) rule: String,
+    /// The pattern of the rule that matched, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pattern: Option<String>,
+    /// What was rejected, e.g. `shop.users.password`.
+    pub(crate) object: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pattern {
+            Some(pattern) => write!(
+                f,
+                "Policy rule {} ({}) rejects {}",
+                self.rule, pattern, self.object
+            ),
+            None => write!(f, "Policy rule {} rejects {}", self.rule, self.object),
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl Policy {
+    /// Load a policy, parsed as YAML for `.yaml`/`.yml` files and TOML otherwise.
+    ///
+    /// The path may come from an agent, so why a file can't be used is only
+    /// logged: parse errors quote the file, and read errors tell whether it
+    /// exists.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let invalid = |error: &dyn fmt::Display| {
+            tracing::warn!(path = %path.display(), %error, "invalid policy file");
+            anyhow::anyhow!("Invalid policy file {}", path.display())
+        };
+        let content = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let policy: Policy = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| invalid(&e))?,
+            _ => toml::from_str(&content).map_err(|e| invalid(&e))?,
+        };
+
+        if let Some(kind) = policy
+            .allowed_statements
+            .iter()
+            .find(|kind| !STATEMENT_KINDS.contains(&kind.as_str()))
+        {
+            return Err(anyhow::anyhow!(
+                "Unknown statement kind {} in {}, expected one of {}",
+                kind,
+                path.display(),
+                STATEMENT_KINDS.join(", ")
+            ));
+        }
+        Ok(policy)
+    }
+
+    /// Check `statement` and everything nested in it, including subqueries
+    /// and CTEs.
+    pub(crate) fn check(&self, statement: &Statement) -> Result<(), PolicyViolation> {
+        self.check_kind(statement_kind(statement))?;
+        self.check_objects(statement)
+    }
+
+    /// Like [`Policy::check`], without checking the kind of `statement`.
+    pub(crate) fn check_objects(&self, statement: &Statement) -> Result<(), PolicyViolation> {
+        if let Some(result) = self.check_show(statement) {
+            return result;
+        }
+
+        let mut walk = Walk {
+            policy: self,
+            ctes: cte_names(statement),
+            relations: Vec::new(),
+            aliases: Vec::new(),
+            scopes: Vec::new(),
+            columns: Vec::new(),
+            wildcards: Vec::new(),
+        };
+        if let ControlFlow::Break(violation) = statement.visit(&mut walk) {
+            return Err(violation);
+        }
+        walk.check_columns()
+    }
+
+    /// Check the object a SHOW or DESCRIBE statement names, which is not
+    /// read like a table in a query. Showing the columns of a table counts
+    /// as reading all of them, as for `table.*`. `None` for statements that
+    /// are walked like any other.
+    fn check_show(&self, statement: &Statement) -> Option<Result<(), PolicyViolation>> {
+        let result = match statement {
+            Statement::ShowCreate { obj_type, obj_name } => {
+                let name = qualified_name(obj_name);
+                match obj_type {
+                    ShowCreateObject::Table | ShowCreateObject::View => {
+                        self.check_columns_of(&name)
+                    }
+                    _ => self.check_parent_schema(name.schema.as_deref()),
+                }
+            }
+            Statement::ShowColumns { show_options, .. } => match show_parent(show_options) {
+                Some(table) => self.check_columns_of(&qualified_name(table)),
+                None => Ok(()),
+            },
+            Statement::ExplainTable { table_name, .. } => {
+                self.check_columns_of(&qualified_name(table_name))
+            }
+            Statement::ShowTables { show_options, .. }
+            | Statement::ShowViews { show_options, .. } => {
+                let schema = show_parent(show_options).map(ObjectName::to_string);
+                self.check_parent_schema(schema.as_deref())
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn check_columns_of(&self, table: &QualifiedName) -> Result<(), PolicyViolation> {
+        self.check_table(table)?;
+        self.check_wildcard(table)
+    }
+
+    /// Check the schema an object lives in, the default one when unnamed.
+    fn check_parent_schema(&self, schema: Option<&str>) -> Result<(), PolicyViolation> {
+        match schema.or(self.default_schema.as_deref()) {
+            Some(schema) => self.check_schema(schema),
+            None if !self.schemas.allow.is_empty() => Err(PolicyViolation {
+                rule: "schemas.allow".to_string(),
+                pattern: None,
+                object: "the current schema".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_kind(&self, kind: &str) -> Result<(), PolicyViolation> {
+        if !self.allowed_statements.is_empty() && !self.allowed_statements.iter().any(|k| k == kind)
+        {
+            return Err(PolicyViolation {
+                rule: "allowed_statements".to_string(),
+                pattern: None,
+                object: kind.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_schema(&self, schema: &str) -> Result<(), PolicyViolation> {
+        check_rules("schemas", &self.schemas, &[schema], schema)
+    }
+
+    pub(crate) fn check_table(&self, table: &QualifiedName) -> Result<(), PolicyViolation> {
+        let schema = table.schema.as_ref().or(self.default_schema.as_ref());
+        let object = match schema {
+            Some(schema) => format!("{}.{}", schema, table.name),
+            None => table.name.clone(),
+        };
+        match schema {
+            Some(schema) => self.check_schema(schema)?,
+            // the schema can't be known, so it can't be on an allow list
+            None if !self.schemas.allow.is_empty() => {
+                return Err(PolicyViolation {
+                    rule: "schemas.allow".to_string(),
+                    pattern: None,
+                    object,
+                });
+            }
+            None => {}
+        }
+
+        let mut parts = vec![table.name.as_str()];
+        if let Some(schema) = schema {
+            parts.insert(0, schema);
+        }
+        check_rules("tables", &self.tables, &parts, &object)
+    }
+
+    fn check_column(&self, table: &QualifiedName, column: &str) -> Result<(), PolicyViolation> {
+        let schema = table.schema.as_ref().or(self.default_schema.as_ref());
+        let mut parts = vec![table.name.as_str(), column];
+        if let Some(schema) = schema {
+            parts.insert(0, schema);
+        }
+        check_rules("columns", &self.columns, &parts, &parts.join("."))
+    }
+
+    /// Reject `table.*` when a column rule could hide part of the table.
+    fn check_wildcard(&self, table: &QualifiedName) -> Result<(), PolicyViolation> {
+        let schema = table.schema.as_ref().or(self.default_schema.as_ref());
+        let mut parts = vec![table.name.as_str(), "*"];
+        if let Some(schema) = schema {
+            parts.insert(0, schema);
+        }
+        let object = parts.join(".");
+
+        if let Some(pattern) = self
+            .columns
+            .deny
+            .iter()
+            .find(|p| matches_table(p, &parts[..parts.len() - 1]))
+        {
+            return Err(PolicyViolation {
+                rule: "columns.deny".to_string(),
+                pattern: Some(pattern.clone()),
+                object,
+            });
+        }
+        if !self.columns.allow.is_empty() && !matches_any(&self.columns.allow, &parts) {
+            return Err(PolicyViolation {
+                rule: "columns.allow".to_string(),
+                pattern: None,
+                object,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_function(&self, name: &ObjectName) -> Result<(), PolicyViolation> {
+        let name = name.to_string();
+        let base = split_name(&name).pop().unwrap_or_default();
+        match self
+            .forbidden_functions
+            .iter()
+            .find(|f| f.eq_ignore_ascii_case(&base))
+        {
+            Some(pattern) => Err(PolicyViolation {
+                rule: "forbidden_functions".to_string(),
+                pattern: Some(pattern.clone()),
+                object: name,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The `allowed_statements` name of `statement`.
+pub(crate) fn statement_kind(statement: &Statement) -> &'static str {
+    match statement {
+        Statement::Query(_) => "select",
+        Statement::Insert { .. } => "insert",
+        Statement::Update { .. } => "update",
+        Statement::Delete { .. } => "delete",
+        Statement::CreateTable { .. } => "create_table",
+        Statement::AlterTable { .. } => "alter_table",
+        Statement::Truncate { .. } => "truncate",
+        Statement::CreateIndex { .. } => "create_index",
+        Statement::CreateView { .. } => "create_view",
+        Statement::CreateSchema { .. } | Statement::CreateDatabase { .. } => "create_schema",
+        Statement::Drop { object_type, .. } => match object_type {
+            ObjectType::Table => "drop_table",
+            ObjectType::Index => "drop_index",
+            ObjectType::View => "drop_view",
+            ObjectType::Schema | ObjectType::Database => "drop_schema",
+            _ => "other",
+        },
+        Statement::ShowTables { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ShowCreate { .. }
+        | Statement::ShowDatabases { .. }
+        | Statement::ShowSchemas { .. }
+        | Statement::ShowViews { .. }
+        | Statement::ShowVariables { .. }
+        | Statement::ShowStatus { .. }
+        | Statement::ShowCollation { .. }
+        | Statement::ShowFunctions { .. } => "show",
+        Statement::ExplainTable { .. } => "describe",
+        Statement::Explain { .. } => "explain",
+        _ => "other",
+    }
+}
+
+/// Collects what a statement touches; table and function rules are checked
+/// as the tree is walked, column rules once every alias is known.
+struct Walk<'a> {
+    policy: &'a Policy,
+    ctes: HashSet<String>,
+    /// Every table the statement names.
+    relations: Vec<QualifiedName>,
+    /// `(alias, table)` pairs of the whole statement.
+    aliases: Vec<(String, QualifiedName)>,
+    /// `(alias, table)` pairs bound by each query being walked, innermost
+    /// last.
+    scopes: Vec<Vec<(String, QualifiedName)>>,
+    /// Column references, e.g. `["u", "email"]`.
+    columns: Vec<Reference>,
+    /// Wildcards, with the qualifier of `t.*` as their path.
+    wildcards: Vec<Reference>,
+}
+
+/// A column or wildcard as written, with the tables its qualifier was bound
+/// to in the query it appears in. `None` when no enclosing query binds it.
+struct Reference {
+    path: Vec<String>,
+    tables: Option<Vec<QualifiedName>>,
+}
+
+impl Visitor for Walk<'_> {
+    type Break = PolicyViolation;
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<Self::Break> {
+        let Some(table) = self.table(relation) else {
+            return ControlFlow::Continue(());
+        };
+        into_flow(self.policy.check_table(&table))?;
+        self.relations.push(table);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, factor: &TableFactor) -> ControlFlow<Self::Break> {
+        let TableFactor::Table { name, alias, .. } = factor else {
+            return ControlFlow::Continue(());
+        };
+        if let (Some(table), Some(alias)) = (self.table(name), alias) {
+            self.aliases.push((alias.name.value.clone(), table));
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        // the projection is visited before FROM, so bind the aliases first
+        let mut scope = Vec::new();
+        self.collect_aliases(&query.body, &mut scope);
+        self.scopes.push(scope);
+        self.collect_wildcards(&query.body);
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_query(&mut self, _query: &Query) -> ControlFlow<Self::Break> {
+        self.scopes.pop();
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_statement(&mut self, statement: &Statement) -> ControlFlow<Self::Break> {
+        match statement {
+            Statement::Insert(insert) => {
+                for column in &insert.columns {
+                    self.reference(vec![column.value.clone()]);
+                }
+            }
+            Statement::Update { assignments, .. } => {
+                for assignment in assignments {
+                    let target = assignment.target.to_string();
+                    for column in target.trim_matches(|c| c == '(' || c == ')').split(',') {
+                        self.reference(split_name(column.trim()));
+                    }
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        match expr {
+            Expr::Identifier(ident) => self.reference(vec![ident.value.clone()]),
+            Expr::CompoundIdentifier(idents) => {
+                self.reference(idents.iter().map(|i| i.value.clone()).collect())
+            }
+            Expr::Function(function) => {
+                return into_flow(self.policy.check_function(&function.name));
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl Walk<'_> {
+    /// `None` for references to a CTE, which are not tables.
+    fn table(&self, name: &ObjectName) -> Option<QualifiedName> {
+        let mut parts = split_name(&name.to_string());
+        let name = parts.pop().unwrap_or_default();
+        if parts.is_empty() && self.ctes.contains(&name.to_lowercase()) {
+            return None;
+        }
+        Some(QualifiedName {
+            schema: parts.pop(),
+            name,
+        })
+    }
+
+    /// Aliases bound by the FROM clauses of a query body. Both sides of a
+    /// UNION share one scope, which only errs on the side of checking more.
+    fn collect_aliases(&self, body: &SetExpr, scope: &mut Vec<(String, QualifiedName)>) {
+        match body {
+            SetExpr::Select(select) => {
+                for table in &select.from {
+                    for factor in std::iter::once(&table.relation)
+                        .chain(table.joins.iter().map(|j| &j.relation))
+                    {
+                        let TableFactor::Table {
+                            name,
+                            alias: Some(alias),
+                            ..
+                        } = factor
+                        else {
+                            continue;
+                        };
+                        if let Some(table) = self.table(name) {
+                            scope.push((alias.name.value.clone(), table));
+                        }
+                    }
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.collect_aliases(left, scope);
+                self.collect_aliases(right, scope);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record a column reference, binding its qualifier in the innermost
+    /// query that has an alias of that name.
+    fn reference(&mut self, path: Vec<String>) {
+        let tables = self.bind(&path[..path.len().saturating_sub(1)]);
+        self.columns.push(Reference { path, tables });
+    }
+
+    fn bind(&self, qualifier: &[String]) -> Option<Vec<QualifiedName>> {
+        let [alias] = qualifier else {
+            return None;
+        };
+        self.scopes
+            .iter()
+            .rev()
+            .map(|scope| {
+                scope
+                    .iter()
+                    .filter(|(a, _)| a.eq_ignore_ascii_case(alias))
+                    .map(|(_, table)| table.clone())
+                    .collect::<Vec<_>>()
+            })
+            .find(|tables| !tables.is_empty())
+    }
+
+    fn collect_wildcards(&mut self, body: &SetExpr) {
+        match body {
+            SetExpr::Select(select) => {
+                for item in &select.projection {
+                    match item {
+                        SelectItem::Wildcard(_) => self.wildcards.push(Reference {
+                            path: Vec::new(),
+                            tables: None,
+                        }),
+                        SelectItem::QualifiedWildcard(..) => {
+                            let item = item.to_string();
+                            let qualifier = item.trim_end_matches(".*");
+                            let path = split_name(qualifier);
+                            let tables = self.bind(&path);
+                            self.wildcards.push(Reference { path, tables });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.collect_wildcards(left);
+                self.collect_wildcards(right);
+            }
+            _ => {}
+        }
+    }
+
+    /// Tables a reference can be to: those its qualifier was bound to, every
+    /// table of the statement for an unqualified column, and every table of
+    /// an alias no enclosing query binds, e.g. in an UPDATE.
+    fn candidates(&self, reference: &Reference, qualifier: &[String]) -> Vec<QualifiedName> {
+        if let Some(tables) = &reference.tables {
+            return tables.clone();
+        }
+        match qualifier {
+            [] => self.relations.clone(),
+            [alias] => {
+                let aliased: Vec<QualifiedName> = self
+                    .aliases
+                    .iter()
+                    .filter(|(a, _)| a.eq_ignore_ascii_case(alias))
+                    .map(|(_, table)| table.clone())
+                    .collect();
+                if aliased.is_empty() {
+                    vec![QualifiedName {
+                        schema: None,
+                        name: alias.clone(),
+                    }]
+                } else {
+                    aliased
+                }
+            }
+            [.., schema, table] => vec![QualifiedName {
+                schema: Some(schema.clone()),
+                name: table.clone(),
+            }],
+        }
+    }
+
+    fn check_columns(&self) -> Result<(), PolicyViolation> {
+        if self.policy.columns.allow.is_empty() && self.policy.columns.deny.is_empty() {
+            return Ok(());
+        }
+
+        for column in &self.columns {
+            let Some((name, qualifier)) = column.path.split_last() else {
+                continue;
+            };
+            let candidates = self.candidates(column, qualifier);
+            if candidates.is_empty() {
+                // no table in scope, e.g. `SELECT 1`
+                continue;
+            }
+            for table in candidates {
+                self.policy.check_column(&table, name)?;
+            }
+        }
+
+        for wildcard in &self.wildcards {
+            for table in self.candidates(wildcard, &wildcard.path) {
+                self.policy.check_wildcard(&table)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn qualified_name(name: &ObjectName) -> QualifiedName {
+    let mut parts = split_name(&name.to_string());
+    let name = parts.pop().unwrap_or_default();
+    QualifiedName {
+        schema: parts.pop(),
+        name,
+    }
+}
+
+/// The `FROM`/`IN` object of a SHOW statement.
+fn show_parent(options: &ShowStatementOptions) -> Option<&ObjectName> {
+    options.show_in.as_ref()?.parent_name.as_ref()
+}
+
+/// Lowercased names of every CTE defined anywhere in `statement`.
+fn cte_names(statement: &Statement) -> HashSet<String> {
+    struct Ctes(HashSet<String>);
+
+    impl Visitor for Ctes {
+        type Break = ();
+
+        fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+            if let Some(with) = &query.with {
+                for cte in &with.cte_tables {
+                    self.0.insert(cte.alias.name.value.to_lowercase());
+                }
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    let mut ctes = Ctes(HashSet::new());
+    let _ = statement.visit(&mut ctes);
+    ctes.0
+}
+
+fn check_rules(
+    kind: &str,
+    rules: &Rules,
+    parts: &[&str],
+    object: &str,
+) -> Result<(), PolicyViolation> {
+    if let Some(pattern) = rules.deny.iter().find(|p| matches(p, parts)) {
+        return Err(PolicyViolation {
+            rule: format!("{}.deny", kind),
+            pattern: Some(pattern.clone()),
+            object: object.to_string(),
+        });
+    }
+    if !rules.allow.is_empty() && !matches_any(&rules.allow, parts) {
+        return Err(PolicyViolation {
+            rule: format!("{}.allow", kind),
+            pattern: None,
+            object: object.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn matches_any(patterns: &[String], parts: &[&str]) -> bool {
+    patterns.iter().any(|p| matches(p, parts))
+}
+
+/// Whether `pattern` matches the trailing parts of a qualified name, so
+/// `users.password` matches `shop.users.password`.
+fn matches(pattern: &str, parts: &[&str]) -> bool {
+    let pattern = split_name(pattern);
+    pattern.len() <= parts.len()
+        && pattern
+            .iter()
+            .rev()
+            .zip(parts.iter().rev())
+            .all(|(p, part)| glob(&p.to_lowercase(), &part.to_lowercase()))
+}
+
+/// Whether a column `pattern` applies to some column of the table `parts`.
+fn matches_table(pattern: &str, parts: &[&str]) -> bool {
+    let pattern = split_name(pattern);
+    let Some((_, table_pattern)) = pattern.split_last() else {
+        return false;
+    };
+    table_pattern.len() <= parts.len()
+        && table_pattern
+            .iter()
+            .rev()
+            .zip(parts.iter().rev())
+            .all(|(p, part)| glob(&p.to_lowercase(), &part.to_lowercase()))
+}
+
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            text.starts_with(prefix)
+                && (prefix.len()..=text.len())
+                    .filter(|i| text.is_char_boundary(*i))
+                    .any(|i| glob(rest, &text[i..]))
+        }
+    }
+}
+
+fn into_flow(result: Result<(), PolicyViolation>) -> ControlFlow<PolicyViolation> {
+    match result {
+        Ok(()) => ControlFlow::Continue(()),
+        Err(violation) => ControlFlow::Break(violation),
+    }
+}
+
+fn default_forbidden_functions() -> Vec<String> {
+    DEFAULT_FORBIDDEN_FUNCTIONS
+        .iter()
+        .map(|f| f.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    use super::*;
+
+    const POLICY: &str = r#"
+        allowed_statements = ["select", "update"]
+
+        [schemas]
+        deny = ["mysql"]
+
+        [tables]
+        deny = ["shop.audit_*"]
+
+        [columns]
+        deny = ["users.password"]
+    "#;
+
+    fn policy() -> Policy {
+        let mut policy: Policy = toml::from_str(POLICY).unwrap();
+        policy.default_schema = Some("shop".to_string());
+        policy
+    }
+
+    fn check(sql: &str) -> Result<(), PolicyViolation> {
+        let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        policy().check(&statement)
+    }
+
+    fn rule(sql: &str) -> String {
+        check(sql).unwrap_err().rule
+    }
+
+    #[test]
+    fn policy_should_allow_plain_queries() {
+        assert!(check("SELECT id, name FROM users WHERE id = 1").is_ok());
+        assert!(check("SELECT o.* FROM orders o JOIN users u ON u.id = o.user_id").is_ok());
+        assert!(check("UPDATE users SET name = 'x' WHERE id = 1").is_ok());
+        assert!(check("WITH audit_x AS (SELECT 1 AS n) SELECT n FROM audit_x").is_ok());
+        assert!(check("SELECT NOW()").is_ok());
+    }
+
+    #[test]
+    fn policy_should_name_the_rule() {
+        assert_eq!(rule("DELETE FROM users WHERE id = 1"), "allowed_statements");
+        assert_eq!(rule("SELECT * FROM mysql.user"), "schemas.deny");
+        assert_eq!(rule("SELECT id FROM audit_log"), "tables.deny");
+        assert_eq!(rule("SELECT SLEEP(10)"), "forbidden_functions");
+        assert_eq!(rule("SELECT password FROM users"), "columns.deny");
+
+        let violation = check("SELECT id FROM shop.audit_log").unwrap_err();
+        assert_eq!(violation.pattern.as_deref(), Some("shop.audit_*"));
+        assert_eq!(violation.object, "shop.audit_log");
+    }
+
+    #[test]
+    fn policy_should_walk_nested_queries() {
+        let sql = "SELECT id FROM orders WHERE user_id IN (SELECT id FROM mysql.user)";
+        assert_eq!(rule(sql), "schemas.deny");
+        let sql = "WITH t AS (SELECT * FROM audit_log) SELECT * FROM t";
+        assert_eq!(rule(sql), "tables.deny");
+        let sql = "SELECT id FROM (SELECT id, BENCHMARK(1, 1) FROM orders) d";
+        assert_eq!(rule(sql), "forbidden_functions");
+        let sql = "SELECT id FROM orders UNION SELECT u.password FROM users u";
+        assert_eq!(rule(sql), "columns.deny");
+    }
+
+    #[test]
+    fn policy_should_resolve_aliases_and_wildcards() {
+        assert_eq!(rule("SELECT x.password FROM users AS x"), "columns.deny");
+        assert_eq!(rule("SELECT * FROM users"), "columns.deny");
+        assert_eq!(
+            rule("SELECT u.* FROM users u JOIN orders o"),
+            "columns.deny"
+        );
+        assert_eq!(
+            rule("UPDATE users SET password = 'x' WHERE id = 1"),
+            "columns.deny"
+        );
+        assert!(check("SELECT o.* FROM users u JOIN orders o ON u.id = o.user_id").is_ok());
+
+        // an alias of a subquery doesn't hide the outer one
+        let sql = "SELECT x.password, (SELECT 1 FROM orders x) FROM users x";
+        assert_eq!(rule(sql), "columns.deny");
+        let sql =
+            "SELECT id FROM orders x WHERE EXISTS (SELECT 1 FROM users x WHERE x.password = '')";
+        assert_eq!(rule(sql), "columns.deny");
+        let sql = "SELECT x.id FROM users x WHERE EXISTS (SELECT x.password FROM orders y)";
+        assert_eq!(rule(sql), "columns.deny");
+        assert!(
+            check("SELECT x.password FROM orders x WHERE x.id IN (SELECT x.id FROM users x)")
+                .is_ok()
+        );
+
+        let mut policy = policy();
+        policy.allowed_statements.clear();
+        let sql = "INSERT INTO users (name, password) VALUES ('a', 'b')";
+        let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+        assert_eq!(policy.check(&statement).unwrap_err().rule, "columns.deny");
+    }
+
+    #[test]
+    fn policy_should_check_shown_objects() {
+        let mut policy = policy();
+        policy.allowed_statements.clear();
+        let rule = |sql: &str| {
+            let statement = Parser::parse_sql(&MySqlDialect {}, sql).unwrap().remove(0);
+            policy
+                .check(&statement)
+                .err()
+                .map(|violation| violation.rule)
+        };
+
+        assert_eq!(
+            rule("SHOW CREATE TABLE shop.audit_log").unwrap(),
+            "tables.deny"
+        );
+        assert_eq!(
+            rule("SHOW CREATE TABLE mysql.user").unwrap(),
+            "schemas.deny"
+        );
+        assert_eq!(
+            rule("SHOW CREATE PROCEDURE mysql.p").unwrap(),
+            "schemas.deny"
+        );
+        assert_eq!(rule("DESCRIBE audit_log").unwrap(), "tables.deny");
+        assert_eq!(
+            rule("SHOW COLUMNS FROM mysql.user").unwrap(),
+            "schemas.deny"
+        );
+        assert_eq!(rule("SHOW TABLES FROM mysql").unwrap(), "schemas.deny");
+        // the columns of a table with a denied column can't be listed
+        assert_eq!(rule("DESCRIBE users").unwrap(), "columns.deny");
+        assert_eq!(rule("SHOW CREATE TABLE users").unwrap(), "columns.deny");
+
+        assert_eq!(rule("DESCRIBE orders"), None);
+        assert_eq!(rule("SHOW CREATE TABLE shop.orders"), None);
+        assert_eq!(rule("SHOW TABLES"), None);
+    }
+
+    #[test]
+    fn policy_should_load_yaml_and_reject_unknown_kinds() {
+        let yaml = "allowed_statements: [select]\ntables:\n  allow: [orders]\n";
+        let policy: Policy = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(policy.forbidden_functions.len(), 4);
+        let table = QualifiedName::parse("users").unwrap();
+        assert_eq!(policy.check_table(&table).unwrap_err().rule, "tables.allow");
+
+        let path = std::env::temp_dir().join(format!("policy-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "allowed_statements = [\"selects\"]").unwrap();
+        assert!(Policy::load(&path).is_err());
+
+        // neither the content nor the existence of other files shows
+        std::fs::write(&path, "password = hunter2\n").unwrap();
+        let err = Policy::load(&path).unwrap_err().to_string();
+        assert!(!err.contains("hunter2"));
+        std::fs::remove_file(&path).unwrap();
+        let missing = Policy::load(&path).unwrap_err().to_string();
+        assert_eq!(err, missing);
+    }
+}
@@ -0,0 +1,529 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use sqlparser::ast::{Expr, Query, SelectItem, SetExpr, Statement, TableAlias, TableFactor};
+use sqlx::mysql::MySqlConnection;
+use sqlx::{Column, TypeInfo};
+use tokio_stream::StreamExt;
+
+use crate::ident::split_name;
+use crate::value::{bind_params, decode_column};
+
+/// Query result returned by the `query` tool. Rows are positional so that
+/// duplicate column names (e.g. `id` from both sides of a JOIN) survive.
+#[derive(Debug, Serialize)]
+pub(crate) struct ResultSet {
+    pub(crate) columns: Vec<ColumnMeta>,
+    pub(crate) rows: Vec<Vec<Value>>,
+    pub(crate) row_count: usize,
+    /// Rows read from the server, including the one that hit a limit.
+    pub(crate) rows_seen: usize,
+    /// Set when a row or byte limit cut the result short.
+    pub(crate) truncated: bool,
+    pub(crate) elapsed_ms: u64,
+    /// Pass to `query_next` to read the next page of a paginated query.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cursor: Option<String>,
+}
+
+/// Caps applied to a single result; whichever is hit first ends the fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RowLimits {
+    pub(crate) max_rows: usize,
+    /// Budget for the JSON encoded rows.
+    pub(crate) max_bytes: usize,
+}
+
+/// Decoded rows read by [`fetch_limited`].
+#[derive(Debug, Default)]
+pub(crate) struct Page {
+    pub(crate) rows: Vec<Vec<Value>>,
+    pub(crate) rows_seen: usize,
+    pub(crate) truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ColumnMeta {
+    pub(crate) name: String,
+    #[serde(rename = "type")]
+    pub(crate) type_name: String,
+    pub(crate) nullable: Option<bool>,
+    /// Base table the column was read from, when it can be resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) schema: Option<String>,
+    #[serde(skip)]
+    pub(crate) origin_column: Option<String>,
+}
+
+/// Where a result column comes from in the underlying base tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Origin {
+    pub(crate) schema: Option<String>,
+    pub(crate) table: String,
+    pub(crate) column: String,
+}
+
+/// A base table referenced in the FROM clause of a SELECT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TableRef {
+    pub(crate) schema: Option<String>,
+    pub(crate) table: String,
+}
+
+/// Origin of one projection item, before wildcards are expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProjectionOrigin {
+    Column(Option<Origin>),
+    /// `*` or `t.*`, expanding to every column of the listed tables in order.
+    Wildcard(Vec<TableRef>),
+}
+
+struct ScopeEntry {
+    alias: Option<String>,
+    /// `None` for derived tables and CTEs, which have no base table.
+    table: Option<TableRef>,
+    /// Output columns of a derived table or CTE, when they can be resolved.
+    columns: Option<Vec<DerivedColumn>>,
+}
+
+/// Output column of a derived table or CTE: its name, unless it is an
+/// unaliased expression, and its origin.
+type DerivedColumn = (Option<String>, Option<Origin>);
+
+/// CTEs in scope, by lowercase name, innermost last.
+type Ctes = Vec<(String, Option<Vec<DerivedColumn>>)>;
+
+impl ResultSet {
+    pub(crate) fn new(columns: Vec<ColumnMeta>, page: Page, elapsed: Duration) -> Self {
+        Self {
+            row_count: page.rows.len(),
+            columns,
+            rows: page.rows,
+            rows_seen: page.rows_seen,
+            truncated: page.truncated,
+            elapsed_ms: elapsed.as_millis() as u64,
+            cursor: None,
+        }
+    }
+}
+
+/// Stream the rows of `query`, stopping as soon as `limits` is exceeded so a
+/// large result is never buffered in full.
+pub(crate) async fn fetch_limited(
+    conn: &mut MySqlConnection,
+    query: &str,
+    params: &[Value],
+    limits: RowLimits,
+) -> Result<Page, anyhow::Error> {
+    let mut page = Page::default();
+    let mut bytes = 0;
+    let mut stream = bind_params(sqlx::query(query), params).fetch(&mut *conn);
+
+    while let Some(row) = stream.next().await {
+        let row = row?;
+        page.rows_seen += 1;
+        if page.rows.len() >= limits.max_rows {
+            page.truncated = true;
+            break;
+        }
+
+        let values = decode_row(&row);
+        bytes += serde_json::to_vec(&values)?.len();
+        if bytes > limits.max_bytes {
+            page.truncated = true;
+            break;
+        }
+        page.rows.push(values);
+    }
+
+    Ok(page)
+}
+
+pub(crate) fn decode_row(row: &sqlx::mysql::MySqlRow) -> Vec<Value> {
+    (0..sqlx::Row::len(row))
+        .map(|i| decode_column(row, i))
+        .collect()
+}
+
+/// Build column metadata for `query` from the prepared statement description,
+/// resolving origin tables from the parsed statement where possible.
+pub(crate) async fn describe_columns(
+    conn: &mut MySqlConnection,
+    query: &str,
+    statement: &Statement,
+) -> Result<Vec<ColumnMeta>, sqlx::Error> {
+    let describe = sqlx::Executor::describe(&mut *conn, query).await?;
+
+    let mut columns: Vec<ColumnMeta> = describe
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| ColumnMeta {
+            name: column.name().to_string(),
+            type_name: column.type_info().name().to_string(),
+            nullable: describe.nullable(i),
+            table: None,
+            schema: None,
+            origin_column: None,
+        })
+        .collect();
+
+    let origins = expand_origins(conn, projection_origins(statement), &columns).await?;
+    for (column, origin) in columns.iter_mut().zip(origins) {
+        if let Some(origin) = origin {
+            column.table = Some(origin.table);
+            column.schema = origin.schema;
+            column.origin_column = Some(origin.column);
+        }
+    }
+
+    Ok(columns)
+}
+
+/// Expand wildcards against `information_schema` and line the result up with
+/// the actual result columns. Anything that does not match exactly is left
+/// unresolved rather than guessed.
+async fn expand_origins(
+    conn: &mut MySqlConnection,
+    projection: Option<Vec<ProjectionOrigin>>,
+    columns: &[ColumnMeta],
+) -> Result<Vec<Option<Origin>>, sqlx::Error> {
+    let unresolved = vec![None; columns.len()];
+    let Some(projection) = projection else {
+        return Ok(unresolved);
+    };
+
+    let mut origins = Vec::with_capacity(columns.len());
+    for item in projection {
+        match item {
+            ProjectionOrigin::Column(origin) => origins.push(origin),
+            ProjectionOrigin::Wildcard(tables) => {
+                for table in tables {
+                    let names: Vec<String> = sqlx::query_scalar(
+                        r#"
+                          SELECT CAST(COLUMN_NAME AS CHAR)
+                          FROM information_schema.columns
+                          WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+                          ORDER BY ordinal_position
+                        "#,
+                    )
+                    .bind(&table.schema)
+                    .bind(&table.table)
+                    .fetch_all(&mut *conn)
+                    .await?;
+
+                    for name in names {
+                        let start = origins.len();
+                        let matches = columns.get(start).is_some_and(|c| c.name == name);
+                        origins.push(matches.then(|| Origin {
+                            schema: table.schema.clone(),
+                            table: table.table.clone(),
+                            column: name,
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    if origins.len() != columns.len() {
+        return Ok(unresolved);
+    }
+    Ok(origins)
+}
+
+/// Resolve projection items of a plain SELECT to their base tables, looking
+/// through derived tables and CTEs. Returns `None` when the statement shape
+/// is not supported (e.g. UNION) or a wildcard covers a derived table.
+pub(crate) fn projection_origins(statement: &Statement) -> Option<Vec<ProjectionOrigin>> {
+    let Statement::Query(query) = statement else {
+        return None;
+    };
+    query_origins(query, &Vec::new())
+}
+
+fn query_origins(query: &Query, outer: &Ctes) -> Option<Vec<ProjectionOrigin>> {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return None;
+    };
+
+    let mut ctes = outer.clone();
+    for cte in query.with.iter().flat_map(|with| with.cte_tables.iter()) {
+        // a recursive CTE sees itself without any known columns
+        ctes.push((cte.alias.name.value.to_lowercase(), None));
+        let columns = derived_columns(&cte.query, &cte.alias, &ctes);
+        if let Some(last) = ctes.last_mut() {
+            last.1 = columns;
+        }
+    }
+
+    let mut scope = Vec::new();
+    for table in &select.from {
+        for factor in
+            std::iter::once(&table.relation).chain(table.joins.iter().map(|j| &j.relation))
+        {
+            scope.push(scope_entry(factor, &ctes));
+        }
+    }
+
+    let mut origins = Vec::with_capacity(select.projection.len());
+    for item in &select.projection {
+        let origin = match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                ProjectionOrigin::Column(resolve_expr(expr, &scope))
+            }
+            SelectItem::Wildcard(_) => ProjectionOrigin::Wildcard(
+                scope
+                    .iter()
+                    .map(|e| e.table.clone())
+                    .collect::<Option<_>>()?,
+            ),
+            SelectItem::QualifiedWildcard(prefix, _) => {
+                let prefix = prefix.to_string();
+                let parts = split_name(prefix.trim_end_matches(".*"));
+                let entry = lookup(&scope, &parts)?;
+                ProjectionOrigin::Wildcard(vec![entry.table.clone()?])
+            }
+        };
+        origins.push(origin);
+    }
+
+    Some(origins)
+}
+
+/// Output columns of the subquery of a derived table or CTE named `alias`.
+/// `None` when they can't be named without the server, e.g. for a wildcard.
+fn derived_columns(query: &Query, alias: &TableAlias, ctes: &Ctes) -> Option<Vec<DerivedColumn>> {
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return None;
+    };
+    let mut columns = select
+        .projection
+        .iter()
+        .zip(query_origins(query, ctes)?)
+        .map(|(item, origin)| match origin {
+            ProjectionOrigin::Column(origin) => Some((output_name(item), origin)),
+            ProjectionOrigin::Wildcard(_) => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // `AS d (a, b)` renames the columns by position
+    if !alias.columns.is_empty() {
+        if alias.columns.len() != columns.len() {
+            return None;
+        }
+        for (column, name) in columns.iter_mut().zip(&alias.columns) {
+            column.0 = Some(name.name.value.clone());
+        }
+    }
+    Some(columns)
+}
+
+/// Name of a projection item that can be referenced from an outer query.
+fn output_name(item: &SelectItem) -> Option<String> {
+    match item {
+        SelectItem::ExprWithAlias { alias, .. } => Some(alias.value.clone()),
+        SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Some(ident.value.clone()),
+        SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+            idents.last().map(|ident| ident.value.clone())
+        }
+        _ => None,
+    }
+}
+
+fn scope_entry(factor: &TableFactor, ctes: &Ctes) -> ScopeEntry {
+    match factor {
+        TableFactor::Table { name, alias, .. } => {
+            let mut parts = split_name(&name.to_string());
+            let table = parts.pop().unwrap_or_default();
+            let schema = parts.pop();
+            let alias = alias.as_ref().map(|a| a.name.value.clone());
+            let cte = ctes
+                .iter()
+                .rev()
+                .find(|(name, _)| schema.is_none() && *name == table.to_lowercase());
+            match cte {
+                Some((_, columns)) => ScopeEntry {
+                    alias: alias.or(Some(table)),
+                    table: None,
+                    columns: columns.clone(),
+                },
+                None => ScopeEntry {
+                    alias,
+                    table: Some(TableRef { schema, table }),
+                    columns: None,
+                },
+            }
+        }
+        TableFactor::Derived {
+            subquery, alias, ..
+        } => ScopeEntry {
+            alias: alias.as_ref().map(|a| a.name.value.clone()),
+            table: None,
+            columns: alias
+                .as_ref()
+                .and_then(|alias| derived_columns(subquery, alias, ctes)),
+        },
+        _ => ScopeEntry {
+            alias: None,
+            table: None,
+            columns: None,
+        },
+    }
+}
+
+fn resolve_expr(expr: &Expr, scope: &[ScopeEntry]) -> Option<Origin> {
+    match expr {
+        Expr::Nested(inner) => resolve_expr(inner, scope),
+        Expr::Identifier(ident) => match scope {
+            [entry] => origin(entry, &ident.value),
+            _ => None,
+        },
+        Expr::CompoundIdentifier(idents) => {
+            let (column, qualifier) = idents.split_last()?;
+            let qualifier: Vec<String> = qualifier.iter().map(|i| i.value.clone()).collect();
+            origin(lookup(scope, &qualifier)?, &column.value)
+        }
+        _ => None,
+    }
+}
+
+fn origin(entry: &ScopeEntry, column: &str) -> Option<Origin> {
+    let Some(table) = &entry.table else {
+        // a derived column is only followed when its name is unambiguous
+        let mut found = entry.columns.as_ref()?.iter().filter(|(name, _)| {
+            name.as_ref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(column))
+        });
+        let (_, origin) = found.next()?;
+        return found.next().is_none().then(|| origin.clone()).flatten();
+    };
+    Some(Origin {
+        schema: table.schema.clone(),
+        table: table.table.clone(),
+        column: column.to_string(),
+    })
+}
+
+/// Find the FROM entry a qualifier (`alias`, `table` or `schema.table`) refers to.
+fn lookup<'a>(scope: &'a [ScopeEntry], qualifier: &[String]) -> Option<&'a ScopeEntry> {
+    let mut found = scope
+        .iter()
+        .filter(|entry| match (qualifier, &entry.alias) {
+            ([name], Some(alias)) => name.eq_ignore_ascii_case(alias),
+            ([name], None) => entry
+                .table
+                .as_ref()
+                .is_some_and(|t| name.eq_ignore_ascii_case(&t.table)),
+            ([schema, name], None) => entry.table.as_ref().is_some_and(|t| {
+                name.eq_ignore_ascii_case(&t.table)
+                    && t.schema
+                        .as_ref()
+                        .is_some_and(|s| schema.eq_ignore_ascii_case(s))
+            }),
+            _ => false,
+        });
+    let entry = found.next()?;
+    found.next().is_none().then_some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Statement {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        sqlparser::parser::Parser::parse_sql(&dialect, sql)
+            .unwrap()
+            .remove(0)
+    }
+
+    fn column(schema: Option<&str>, table: &str, column: &str) -> ProjectionOrigin {
+        ProjectionOrigin::Column(Some(Origin {
+            schema: schema.map(String::from),
+            table: table.to_string(),
+            column: column.to_string(),
+        }))
+    }
+
+    #[test]
+    fn projection_origins_should_resolve_aliases() {
+        let stmt = parse(
+            "SELECT u.id, `shop`.`orders`.id AS order_id, orders.total, (u.email), COUNT(*) \
+             FROM users u JOIN shop.orders ON orders.user_id = u.id",
+        );
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![
+                column(None, "users", "id"),
+                column(Some("shop"), "orders", "id"),
+                column(Some("shop"), "orders", "total"),
+                column(None, "users", "email"),
+                ProjectionOrigin::Column(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn projection_origins_should_handle_wildcards() {
+        let stmt = parse("SELECT name, * FROM users");
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![
+                column(None, "users", "name"),
+                ProjectionOrigin::Wildcard(vec![TableRef {
+                    schema: None,
+                    table: "users".to_string()
+                }]),
+            ]
+        );
+
+        let stmt = parse("WITH t AS (SELECT 1 AS a) SELECT * FROM t");
+        assert!(projection_origins(&stmt).is_none());
+
+        let stmt = parse("SELECT a FROM users UNION SELECT b FROM orders");
+        assert!(projection_origins(&stmt).is_none());
+    }
+
+    #[test]
+    fn projection_origins_should_see_through_derived_tables() {
+        let stmt = parse(
+            "SELECT d.x, y, d.id FROM (SELECT u.email AS x, LOWER(email) AS y, id FROM users u) d",
+        );
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![
+                column(None, "users", "email"),
+                ProjectionOrigin::Column(None),
+                column(None, "users", "id"),
+            ]
+        );
+
+        let stmt = parse(
+            "WITH a (uid) AS (SELECT id FROM shop.users), b AS (SELECT uid FROM a) \
+             SELECT b.uid FROM b",
+        );
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![column(Some("shop"), "users", "id")]
+        );
+
+        let stmt = parse("SELECT email FROM (SELECT * FROM users) d");
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![ProjectionOrigin::Column(None)]
+        );
+    }
+
+    #[test]
+    fn projection_origins_should_not_guess_ambiguous_columns() {
+        let stmt = parse("SELECT name FROM users, orders");
+        assert_eq!(
+            projection_origins(&stmt).unwrap(),
+            vec![ProjectionOrigin::Column(None)]
+        );
+    }
+}
@@ -2,10 +2,18 @@ use sqlx::{
     Connection, Executor, MySqlConnection, MySqlPool,
     migrate::{MigrationSource, Migrator},
 };
-use std::{path::Path, thread};
+use std::{
+    path::{Path, PathBuf},
+    thread,
+};
 use tokio::runtime::Runtime;
 use uuid::Uuid;
 
+/// Names the Unix socket of the test server when it isn't at the server's
+/// `@@socket` path on this host, e.g. for a server in a container with its
+/// socket directory mounted.
+pub const TEST_SOCKET_ENV: &str = "MYSQL_MCP_TEST_SOCKET";
+
 #[derive(Debug)]
 pub struct TestMysql {
     pub server_url: String,
@@ -66,6 +74,21 @@ impl TestMysql {
     pub async fn get_pool(&self) -> MySqlPool {
         MySqlPool::connect(&self.url()).await.unwrap()
     }
+
+    /// Path of the Unix socket the server listens on: the one named by
+    /// [`TEST_SOCKET_ENV`], else the server's own when it is reachable from
+    /// here. `None` when neither is, e.g. for a server in a container.
+    pub async fn socket(&self) -> Option<PathBuf> {
+        if let Some(socket) = std::env::var_os(TEST_SOCKET_ENV) {
+            return Some(socket.into());
+        }
+        let mut conn = MySqlConnection::connect(&self.server_url).await.unwrap();
+        let (socket,): (Option<String>,) = sqlx::query_as("SELECT @@socket")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        socket.map(PathBuf::from).filter(|path| path.exists())
+    }
 }
 
 impl Drop for TestMysql {
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::{MySqlConnectOptions, MySqlConnection, MySqlSslMode};
+use sqlx::{Executor, Row};
+
+/// TLS settings of a connection. Unset fields keep what the connection URL
+/// says, so `?ssl-mode=...` URLs keep working.
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TlsOptions {
+    #[schemars(
+        description = "Whether and how TLS is used, defaults to verify_ca when a ca is given and to preferred otherwise"
+    )]
+    pub ssl_mode: Option<SslMode>,
+    #[schemars(description = "PEM file of the CA certificates the server is verified against")]
+    pub ca: Option<PathBuf>,
+    #[schemars(description = "PEM file of the client certificate, requires client_key")]
+    pub client_cert: Option<PathBuf>,
+    #[schemars(description = "PEM file of the client private key, requires client_cert")]
+    pub client_key: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SslMode {
+    /// Never use TLS.
+    Disabled,
+    /// Use TLS when the server supports it.
+    Preferred,
+    /// Fail unless TLS is used, without verifying the server.
+    Required,
+    /// Verify the server certificate against the CA.
+    VerifyCa,
+    /// Also verify that the certificate matches the host name.
+    VerifyIdentity,
+}
+
+/// TLS state of a live session, reported by `connection_status`.
+#[derive(Debug, Serialize)]
+pub(crate) struct TlsStatus {
+    /// e.g. `TLSv1.3`, `None` for an unencrypted session.
+    pub(crate) version: Option<String>,
+    pub(crate) cipher: Option<String>,
+}
+
+impl TlsOptions {
+    pub(crate) fn apply(
+        &self,
+        mut options: MySqlConnectOptions,
+    ) -> Result<MySqlConnectOptions, Error> {
+        if self.client_cert.is_some() != self.client_key.is_some() {
+            return Err(anyhow::anyhow!(
+                "tls.client_cert and tls.client_key must be given together"
+            ));
+        }
+        for (field, path) in [
+            ("tls.ca", &self.ca),
+            ("tls.client_cert", &self.client_cert),
+            ("tls.client_key", &self.client_key),
+        ] {
+            if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+                return Err(anyhow::anyhow!(
+                    "{} {} is not a file",
+                    field,
+                    path.display()
+                ));
+            }
+        }
+
+        if let Some(mode) = self.ssl_mode {
+            if mode == SslMode::Disabled && self.client_cert.is_some() {
+                return Err(anyhow::anyhow!(
+                    "tls.client_cert has no effect with ssl_mode disabled"
+                ));
+            }
+            options = options.ssl_mode(mode.into());
+        }
+        if let Some(ca) = &self.ca {
+            // a CA is given to verify the server, which the default mode
+            // wouldn't do
+            let verified = matches!(
+                options.get_ssl_mode(),
+                MySqlSslMode::VerifyCa | MySqlSslMode::VerifyIdentity
+            );
+            if self.ssl_mode.is_none() && !verified {
+                options = options.ssl_mode(MySqlSslMode::VerifyCa);
+            }
+            options = options.ssl_ca(ca);
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert, &self.client_key) {
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        Ok(options)
+    }
+}
+
+impl From<SslMode> for MySqlSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disabled => MySqlSslMode::Disabled,
+            SslMode::Preferred => MySqlSslMode::Preferred,
+            SslMode::Required => MySqlSslMode::Required,
+            SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+            SslMode::VerifyIdentity => MySqlSslMode::VerifyIdentity,
+        }
+    }
+}
+
+/// Read the negotiated TLS version and cipher of the session on `db`.
+pub(crate) async fn tls_status(db: &mut MySqlConnection) -> Result<TlsStatus, Error> {
+    // SHOW STATUS can't be prepared, so it goes over the text protocol
+    let rows = db
+        .fetch_all("SHOW SESSION STATUS WHERE Variable_name IN ('Ssl_version', 'Ssl_cipher')")
+        .await?;
+    let mut status = TlsStatus {
+        version: None,
+        cipher: None,
+    };
+    for row in rows {
+        let name: String = row.try_get(0)?;
+        let value: String = row.try_get(1)?;
+        let value = Some(value).filter(|v| !v.is_empty());
+        match name.as_str() {
+            "Ssl_version" => status.version = value,
+            "Ssl_cipher" => status.cipher = value,
+            _ => {}
+        }
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn tls_options_should_map_to_connect_options() {
+        let options = MySqlConnectOptions::from_str("mysql://root@localhost/shop").unwrap();
+        let tls: TlsOptions =
+            serde_json::from_value(serde_json::json!({ "ssl_mode": "verify_identity" })).unwrap();
+        let options = tls.apply(options).unwrap();
+        assert!(matches!(
+            options.get_ssl_mode(),
+            MySqlSslMode::VerifyIdentity
+        ));
+
+        let tls = TlsOptions {
+            client_cert: Some("cert.pem".into()),
+            ..Default::default()
+        };
+        let err = tls.apply(MySqlConnectOptions::new()).unwrap_err();
+        assert!(err.to_string().contains("client_key"));
+
+        let tls = TlsOptions {
+            ca: Some("/nonexistent/ca.pem".into()),
+            ..Default::default()
+        };
+        let err = tls.apply(MySqlConnectOptions::new()).unwrap_err();
+        assert!(err.to_string().contains("tls.ca"));
+    }
+
+    #[test]
+    fn tls_options_should_verify_against_a_given_ca() {
+        let ca = std::env::temp_dir().join(format!("ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&ca, "").unwrap();
+        let tls = TlsOptions {
+            ca: Some(ca.clone()),
+            ..Default::default()
+        };
+
+        let options = tls.apply(MySqlConnectOptions::new()).unwrap();
+        assert!(matches!(options.get_ssl_mode(), MySqlSslMode::VerifyCa));
+        let url = "mysql://root@localhost/shop?ssl-mode=VERIFY_IDENTITY";
+        let options = tls
+            .apply(MySqlConnectOptions::from_str(url).unwrap())
+            .unwrap();
+        assert!(matches!(
+            options.get_ssl_mode(),
+            MySqlSslMode::VerifyIdentity
+        ));
+
+        let tls = TlsOptions {
+            ssl_mode: Some(SslMode::Required),
+            ..tls
+        };
+        let options = tls.apply(MySqlConnectOptions::new()).unwrap();
+        assert!(matches!(options.get_ssl_mode(), MySqlSslMode::Required));
+        std::fs::remove_file(&ca).unwrap();
+    }
+}
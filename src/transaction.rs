@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use sqlx::mysql::{MySqlConnection, MySqlPool};
+use sqlx::{Executor, MySql};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::deadline::connection_id;
+use crate::ident;
+
+/// How long an open transaction may sit unused before it is rolled back.
+pub(crate) const TX_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Each open transaction pins a pooled connection, so keep the number bounded.
+pub(crate) const MAX_TRANSACTIONS_PER_CONN: usize = 4;
+
+/// Savepoint set by a [`Scope`] inside an open transaction, a name the
+/// savepoint tools refuse.
+const SCOPE_SAVEPOINT: &str = "mysql_mcp_scope";
+
+type Slots = Arc<Mutex<HashMap<String, Arc<Slot>>>>;
+
+/// Open transactions of a single connection, keyed by transaction id.
+#[derive(Debug, Clone)]
+pub(crate) struct Transactions {
+    inner: Slots,
+    idle_timeout: Duration,
+}
+
+/// A transaction pinned to one pooled connection.
+#[derive(Debug)]
+pub(crate) struct Transaction {
+    pub(crate) db: sqlx::Transaction<'static, MySql>,
+    /// Server side id of the pinned connection.
+    pub(crate) connection_id: u64,
+    /// Savepoint names, oldest first.
+    savepoints: Vec<String>,
+}
+
+/// Statements that can be undone as a unit: a savepoint inside an open
+/// transaction, or a transaction of their own.
+pub(crate) enum Scope {
+    Savepoint(TxGuard),
+    Transaction {
+        db: sqlx::Transaction<'static, MySql>,
+        connection_id: u64,
+    },
+}
+
+/// The transaction is `None` once it has been committed or rolled back, the
+/// slot itself may still be held by the idle watcher.
+#[derive(Debug)]
+struct Slot {
+    tx: Arc<tokio::sync::Mutex<Option<Transaction>>>,
+    expires_at: Mutex<Instant>,
+    idle_timeout: Duration,
+}
+
+/// Exclusive access to an open transaction. Statements on the same
+/// transaction are serialized, and the idle timer restarts when it is dropped.
+pub(crate) struct TxGuard {
+    guard: OwnedMutexGuard<Option<Transaction>>,
+    slot: Arc<Slot>,
+}
+
+impl Transactions {
+    pub(crate) fn new(idle_timeout: Duration) -> Self {
+        Self {
+            inner: Default::default(),
+            idle_timeout,
+        }
+    }
+
+    /// Start a transaction on a connection of `pool` and return its id.
+    pub(crate) async fn begin(&self, pool: &MySqlPool) -> Result<String, Error> {
+        if self.inner.lock().unwrap().len() >= MAX_TRANSACTIONS_PER_CONN {
+            return Err(too_many());
+        }
+
+        let mut db = pool.begin().await?;
+        let connection_id = connection_id(&mut db).await?;
+
+        let slot = Arc::new(Slot {
+            tx: Arc::new(tokio::sync::Mutex::new(Some(Transaction {
+                db,
+                connection_id,
+                savepoints: Vec::new(),
+            }))),
+            expires_at: Mutex::new(Instant::now() + self.idle_timeout),
+            idle_timeout: self.idle_timeout,
+        });
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let inserted = {
+            let mut slots = self.inner.lock().unwrap();
+            // other calls may have taken the free places while this one connected
+            let free = slots.len() < MAX_TRANSACTIONS_PER_CONN;
+            if free {
+                slots.insert(id.clone(), slot.clone());
+            }
+            free
+        };
+        if !inserted {
+            let tx = slot.tx.lock().await.take().expect("not shared yet");
+            tx.db.rollback().await?;
+            return Err(too_many());
+        }
+
+        tokio::spawn(watch(Arc::downgrade(&self.inner), id.clone(), slot));
+        Ok(id)
+    }
+
+    /// Lock the transaction `id` to run a statement on it.
+    pub(crate) async fn get(&self, id: &str) -> Result<TxGuard, Error> {
+        let slot = self.slot(id)?;
+        let guard = slot.tx.clone().lock_owned().await;
+        if guard.is_none() {
+            return Err(not_found());
+        }
+        slot.touch();
+        Ok(TxGuard { guard, slot })
+    }
+
+    /// Open a [`Scope`] inside the transaction `id`, or in a new transaction
+    /// on `pool` when there is none.
+    pub(crate) async fn scope(&self, pool: &MySqlPool, id: Option<&str>) -> Result<Scope, Error> {
+        match id {
+            Some(id) => {
+                let mut tx = self.get(id).await?;
+                let sql = format!("SAVEPOINT {}", ident::quote(SCOPE_SAVEPOINT));
+                tx.db.execute(sql.as_str()).await?;
+                Ok(Scope::Savepoint(tx))
+            }
+            None => {
+                let mut db = pool.begin().await?;
+                let connection_id = connection_id(&mut db).await?;
+                Ok(Scope::Transaction { db, connection_id })
+            }
+        }
+    }
+
+    /// Remove the transaction `id` so it can be committed or rolled back.
+    pub(crate) async fn finish(&self, id: &str) -> Result<Transaction, Error> {
+        let slot = self.slot(id)?;
+        let tx = slot.tx.lock().await.take().ok_or_else(not_found)?;
+        self.inner.lock().unwrap().remove(id);
+        Ok(tx)
+    }
+
+    /// Roll back every open transaction.
+    pub(crate) fn clear(&self) {
+        let slots = std::mem::take(&mut *self.inner.lock().unwrap());
+        for (id, slot) in slots {
+            // wait for a statement still running on it before rolling back
+            tokio::spawn(async move { slot.rollback(&id).await });
+        }
+    }
+
+    fn slot(&self, id: &str) -> Result<Arc<Slot>, Error> {
+        self.inner
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+}
+
+impl Transaction {
+    pub(crate) fn savepoints(&self) -> &[String] {
+        &self.savepoints
+    }
+
+    /// Set a savepoint. Reusing a name moves it to the top of the stack, as
+    /// the server drops the old savepoint of that name.
+    pub(crate) async fn savepoint(&mut self, name: &str) -> Result<(), Error> {
+        let sql = format!("SAVEPOINT {}", quote(name)?);
+        self.db.execute(sql.as_str()).await?;
+        self.savepoints.retain(|s| s != name);
+        self.savepoints.push(name.to_string());
+        Ok(())
+    }
+
+    /// Undo everything since the savepoint `name`, which stays set. Later
+    /// savepoints are dropped.
+    pub(crate) async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), Error> {
+        let position = self.position(name)?;
+        let sql = format!("ROLLBACK TO SAVEPOINT {}", quote(name)?);
+        self.db.execute(sql.as_str()).await?;
+        self.savepoints.truncate(position + 1);
+        Ok(())
+    }
+
+    /// Forget the savepoint `name` and every later one, keeping their changes.
+    pub(crate) async fn release_savepoint(&mut self, name: &str) -> Result<(), Error> {
+        let position = self.position(name)?;
+        let sql = format!("RELEASE SAVEPOINT {}", quote(name)?);
+        self.db.execute(sql.as_str()).await?;
+        self.savepoints.truncate(position);
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Result<usize, Error> {
+        self.savepoints
+            .iter()
+            .position(|s| s == name)
+            .ok_or_else(|| anyhow::anyhow!("Savepoint {} not found", name))
+    }
+}
+
+impl Scope {
+    pub(crate) fn connection_id(&self) -> u64 {
+        match self {
+            Scope::Savepoint(tx) => tx.connection_id,
+            Scope::Transaction { connection_id, .. } => *connection_id,
+        }
+    }
+
+    pub(crate) fn db(&mut self) -> &mut MySqlConnection {
+        match self {
+            Scope::Savepoint(tx) => &mut tx.db,
+            Scope::Transaction { db, .. } => db,
+        }
+    }
+
+    /// Keep the changes made in the scope.
+    pub(crate) async fn keep(self) -> Result<(), Error> {
+        match self {
+            Scope::Savepoint(mut tx) => {
+                let sql = format!("RELEASE SAVEPOINT {}", ident::quote(SCOPE_SAVEPOINT));
+                tx.db.execute(sql.as_str()).await?;
+            }
+            Scope::Transaction { db, .. } => db.commit().await?,
+        }
+        Ok(())
+    }
+
+    /// Undo the changes made in the scope.
+    pub(crate) async fn undo(self) -> Result<(), Error> {
+        match self {
+            Scope::Savepoint(mut tx) => {
+                let sql = format!("ROLLBACK TO SAVEPOINT {}", ident::quote(SCOPE_SAVEPOINT));
+                tx.db.execute(sql.as_str()).await?;
+                let sql = format!("RELEASE SAVEPOINT {}", ident::quote(SCOPE_SAVEPOINT));
+                tx.db.execute(sql.as_str()).await?;
+            }
+            Scope::Transaction { db, .. } => db.rollback().await?,
+        }
+        Ok(())
+    }
+}
+
+fn quote(name: &str) -> Result<String, Error> {
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Savepoint name must not be empty"));
+    }
+    // savepoint names are case insensitive
+    if name.eq_ignore_ascii_case(SCOPE_SAVEPOINT) {
+        return Err(anyhow::anyhow!(
+            "Savepoint name {} is reserved for dry runs and row caps",
+            name
+        ));
+    }
+    Ok(ident::quote(name))
+}
+
+impl Default for Transactions {
+    fn default() -> Self {
+        Self::new(TX_IDLE_TIMEOUT)
+    }
+}
+
+impl Slot {
+    fn expires_at(&self) -> Instant {
+        *self.expires_at.lock().unwrap()
+    }
+
+    fn touch(&self) {
+        *self.expires_at.lock().unwrap() = Instant::now() + self.idle_timeout;
+    }
+
+    async fn rollback(&self, id: &str) {
+        let tx = self.tx.lock().await.take();
+        if let Some(tx) = tx {
+            rollback(id, tx).await;
+        }
+    }
+}
+
+/// Roll the transaction back once it has been idle for its timeout.
+async fn watch(slots: Weak<Mutex<HashMap<String, Arc<Slot>>>>, id: String, slot: Arc<Slot>) {
+    loop {
+        tokio::time::sleep_until(slot.expires_at().into()).await;
+        let mut guard = slot.tx.lock().await;
+        if guard.is_none() {
+            return;
+        }
+        // used again while we slept
+        if slot.expires_at() > Instant::now() {
+            continue;
+        }
+
+        if let Some(slots) = slots.upgrade() {
+            slots.lock().unwrap().remove(&id);
+        }
+        let tx = guard.take().expect("checked above");
+        drop(guard);
+        tracing::info!(tx_id = id, "rolling back idle transaction");
+        rollback(&id, tx).await;
+        return;
+    }
+}
+
+async fn rollback(id: &str, tx: Transaction) {
+    if let Err(e) = tx.db.rollback().await {
+        tracing::warn!(tx_id = id, error = %e, "failed to roll back transaction");
+    }
+}
+
+fn too_many() -> Error {
+    anyhow::anyhow!("Too many open transactions, commit or roll back one first")
+}
+
+fn not_found() -> Error {
+    anyhow::anyhow!("Transaction not found or rolled back")
+}
+
+impl Deref for TxGuard {
+    type Target = Transaction;
+
+    fn deref(&self) -> &Transaction {
+        self.guard.as_ref().expect("checked when locked")
+    }
+}
+
+impl DerefMut for TxGuard {
+    fn deref_mut(&mut self) -> &mut Transaction {
+        self.guard.as_mut().expect("checked when locked")
+    }
+}
+
+impl Drop for TxGuard {
+    fn drop(&mut self) {
+        self.slot.touch();
+    }
+}
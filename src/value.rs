@@ -0,0 +1,106 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::{Value, json};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use sqlx::mysql::{MySqlArguments, MySqlRow, types::MySqlTime};
+use sqlx::query::Query;
+use sqlx::types::chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{Column, MySql, Row, TypeInfo, ValueRef};
+
+/// Marker key wrapping base64 encoded binary values, e.g. `{"$base64": "AAE="}`.
+pub(crate) const BASE64_MARKER: &str = "$base64";
+/// Marker key for values that could not be decoded, e.g. `{"$error": "..."}`.
+pub(crate) const ERROR_MARKER: &str = "$error";
+
+/// Decode the column at `index` into a JSON value based on its MySQL type.
+///
+/// SQL `NULL` always maps to JSON `null`; a value that exists but cannot be
+/// decoded is reported as `{"$error": "..."}` so the two are never confused.
+pub(crate) fn decode_column(row: &MySqlRow, index: usize) -> Value {
+    let type_name = row.columns()[index].type_info().name();
+
+    match row.try_get_raw(index) {
+        Ok(raw) if raw.is_null() => return Value::Null,
+        Ok(_) => {}
+        Err(e) => return error_value(type_name, e),
+    }
+
+    let decoded = match type_name {
+        "BOOLEAN" | "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            row.try_get_unchecked::<i64, _>(index).map(|v| json!(v))
+        }
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" | "BIT" => row.try_get_unchecked::<u64, _>(index).map(|v| json!(v)),
+        "YEAR" => row.try_get_unchecked::<u16, _>(index).map(|v| json!(v)),
+        // go through the shortest decimal representation so 0.1 stays 0.1
+        "FLOAT" => row
+            .try_get_unchecked::<f32, _>(index)
+            .map(|v| json!(v.to_string().parse::<f64>().unwrap_or(v as f64))),
+        "DOUBLE" => row.try_get_unchecked::<f64, _>(index).map(|v| json!(v)),
+        // DECIMAL can exceed the precision of a JSON number, keep it exact as a string
+        "DECIMAL" => row.try_get_unchecked::<String, _>(index).map(Value::String),
+        "DATE" => row
+            .try_get_unchecked::<NaiveDate, _>(index)
+            .map(|v| json!(v.format("%Y-%m-%d").to_string())),
+        "DATETIME" | "TIMESTAMP" => row
+            .try_get_unchecked::<NaiveDateTime, _>(index)
+            .map(|v| json!(v.format("%Y-%m-%dT%H:%M:%S%.f").to_string())),
+        "TIME" => row
+            .try_get_unchecked::<MySqlTime, _>(index)
+            .map(|v| json!(v.to_string())),
+        "JSON" => row.try_get_unchecked::<Value, _>(index),
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "GEOMETRY" => {
+            row.try_get_unchecked::<Vec<u8>, _>(index)
+                .map(|v| base64_value(&v))
+        }
+        // CHAR, VARCHAR, TEXT variants, ENUM and SET
+        _ => row
+            .try_get_unchecked::<String, _>(index)
+            .map(Value::String)
+            .or_else(|_| {
+                row.try_get_unchecked::<Vec<u8>, _>(index)
+                    .map(|v| base64_value(&v))
+            }),
+    };
+
+    decoded.unwrap_or_else(|e| error_value(type_name, e))
+}
+
+/// Bind JSON `params` to the `?` placeholders of `query`, in order. Arrays
+/// and objects are bound as JSON documents.
+pub(crate) fn bind_params<'q>(
+    mut query: Query<'q, MySql, MySqlArguments>,
+    params: &'q [Value],
+) -> Query<'q, MySql, MySqlArguments> {
+    for param in params {
+        query = match param {
+            Value::Null => query.bind(None::<String>),
+            Value::Bool(b) => query.bind(*b),
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => query.bind(i),
+                (None, Some(u)) => query.bind(u),
+                (None, None) => query.bind(n.as_f64()),
+            },
+            Value::String(s) => query.bind(s.as_str()),
+            Value::Array(_) | Value::Object(_) => query.bind(sqlx::types::Json(param)),
+        };
+    }
+    query
+}
+
+/// Number of `?` placeholders in `sql`, ignoring ones inside quotes.
+pub(crate) fn count_placeholders(sql: &str) -> Result<usize, anyhow::Error> {
+    Ok(Tokenizer::new(&MySqlDialect {}, sql)
+        .tokenize()?
+        .iter()
+        .filter(|token| matches!(token, Token::Placeholder(p) if p == "?"))
+        .count())
+}
+
+fn base64_value(bytes: &[u8]) -> Value {
+    json!({ BASE64_MARKER: STANDARD.encode(bytes) })
+}
+
+fn error_value(type_name: &str, error: impl std::fmt::Display) -> Value {
+    json!({ ERROR_MARKER: format!("cannot decode {} value: {}", type_name, error) })
+}
@@ -66,18 +66,43 @@ async fn test_connection_management() -> Result<()> {
     let test_service = setup_service().await?;
     let service = test_service.service;
     let conn_id = test_service.conn_id;
+    let dbname = test_service.tdb.dbname.clone();
 
-    // Test listing tables in public schema
+    // Test listing schemas, which include the test database
+    let tool_result = service
+        .call_tool(CallToolRequestParam {
+            name: "list_schemas".into(),
+            arguments: Some(object!({
+                "conn_id": conn_id.as_str()
+            })),
+        })
+        .await?;
+    let schemas = &tool_result.content[0].raw.as_text().unwrap().text;
+    assert!(schemas.contains(&dbname));
+    assert!(!schemas.contains("performance_schema"));
+
+    // Test listing tables in the connection's current schema
     let tool_result = service
         .call_tool(CallToolRequestParam {
             name: "list_tables".into(),
             arguments: Some(object!({
-                "conn_id": conn_id.as_str(),
-                "schema": "public"
+                "conn_id": conn_id.as_str()
             })),
         })
         .await?;
-    assert!(!tool_result.content.is_empty());
+    let tables = &tool_result.content[0].raw.as_text().unwrap().text;
+    assert!(tables.contains("test_table"));
+
+    // Password references are only accepted in the config file
+    let register_result = service
+        .call_tool(CallToolRequestParam {
+            name: "register".into(),
+            arguments: Some(object!({
+                "connection": { "user": "root", "password_file": "/etc/hostname" }
+            })),
+        })
+        .await;
+    assert!(register_result.is_err());
 
     cleanup_service(service, &conn_id).await?;
     Ok(())
@@ -170,6 +195,26 @@ async fn test_data_operations() -> Result<()> {
         .await?;
     assert!(!query_result.content.is_empty());
 
+    // Query data with a bound parameter
+    let query_result = service
+        .call_tool(CallToolRequestParam {
+            name: "query".into(),
+            arguments: Some(object!({
+                "conn_id": conn_id.as_str(),
+                "query": "SELECT * FROM test_users WHERE email = ?",
+                "params": ["test@example.com"]
+            })),
+        })
+        .await?;
+    assert!(
+        query_result.content[0]
+            .raw
+            .as_text()
+            .unwrap()
+            .text
+            .contains("Test User")
+    );
+
     // Update data
     let update_result = service
         .call_tool(CallToolRequestParam {
@@ -352,3 +397,43 @@ async fn test_schema_operations() -> Result<()> {
     cleanup_service(service, &conn_id).await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_read_only_server() -> Result<()> {
+    let tdb = TestMysql::new(
+        TEST_CONN_STR.to_string(),
+        std::path::Path::new("./fixtures/migrations"),
+    );
+
+    let mut cmd = Command::new("mysql-mcp");
+    cmd.arg("--read-only").arg("stdio");
+    let service = ().serve(TokioChildProcess::new(&mut cmd)?).await?;
+
+    let tools = service.list_all_tools().await?;
+    assert!(tools.iter().any(|tool| tool.name == "query"));
+    assert!(!tools.iter().any(|tool| tool.name == "insert"));
+
+    let tool_result = service
+        .call_tool(CallToolRequestParam {
+            name: "register".into(),
+            arguments: Some(object!({
+                "conn_str": tdb.url()
+            })),
+        })
+        .await?;
+    let conn_id = tool_result.content[0].raw.as_text().unwrap().text.clone();
+
+    let result = service
+        .call_tool(CallToolRequestParam {
+            name: "insert".into(),
+            arguments: Some(object!({
+                "conn_id": conn_id.as_str(),
+                "query": "INSERT INTO test_table (name) VALUES ('x')"
+            })),
+        })
+        .await;
+    assert!(result.is_err());
+
+    cleanup_service(service, conn_id).await?;
+    Ok(())
+}